@@ -0,0 +1,313 @@
+#[cfg(not(feature = "std"))]
+use alloc::collections::VecDeque;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::collections::VecDeque;
+
+// The root of the trie, which stands for the empty prefix.
+const ROOT: usize = 0;
+
+struct Node {
+    // The edges to longer prefixes, sorted by byte.
+    children: Vec<(u8, usize)>,
+    // The node for the longest proper suffix of this prefix
+    // which is also in the trie, like a KMP border.
+    fail: usize,
+    // The pattern which is exactly this prefix, if there is one.
+    pattern: Option<usize>,
+    // The nearest node along the failure links with a pattern,
+    // so all of the patterns ending here can be found quickly.
+    output: Option<usize>,
+    depth: usize,
+}
+
+impl Node {
+    fn new(depth: usize) -> Node {
+        Node{
+            children: Vec::new(),
+            fail: ROOT,
+            pattern: None,
+            output: None,
+            depth,
+        }
+    }
+
+    fn child(&self, b: u8) -> Option<usize> {
+        self.children.binary_search_by_key(&b, |&(c, _)| c)
+            .ok()
+            .map(|i| self.children[i].1)
+    }
+}
+
+// Which match is found when several patterns match starting at the
+// same place. Searching for `["a", "abc"]` in "abcd", leftmost first
+// finds "a", because it's listed first, like an alternation in a regex,
+// while leftmost longest finds "abc".
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MatchKind {
+    LeftmostFirst,
+    LeftmostLongest,
+}
+
+pub struct AhoCorasick {
+    nodes: Vec<Node>,
+    pattern_lens: Vec<usize>,
+    match_kind: MatchKind,
+}
+
+impl AhoCorasick {
+    pub fn new(patterns: &[&str]) -> AhoCorasick {
+        AhoCorasick::with_match_kind(patterns, MatchKind::LeftmostLongest)
+    }
+
+    pub fn with_match_kind(patterns: &[&str], match_kind: MatchKind) -> AhoCorasick {
+        let mut nodes = vec![Node::new(0)];
+
+        // Build a trie of the patterns.
+        for (id, pattern) in patterns.iter().enumerate() {
+            let mut node = ROOT;
+            for &b in pattern.as_bytes() {
+                node = match nodes[node].child(b) {
+                    Some(child) => child,
+                    None => {
+                        let child = nodes.len();
+                        nodes.push(Node::new(nodes[node].depth + 1));
+                        let children = &mut nodes[node].children;
+                        let i = children.binary_search_by_key(&b, |&(c, _)| c).unwrap_err();
+                        children.insert(i, (b, child));
+                        child
+                    },
+                };
+            }
+            // Duplicate patterns are reported as the first of them.
+            if nodes[node].pattern.is_none() {
+                nodes[node].pattern = Some(id);
+            }
+        }
+        if nodes[ROOT].pattern.is_some() {
+            nodes[ROOT].output = Some(ROOT);
+        }
+
+        // Link each node to its failure node, breadth first so that
+        // shallower failure nodes are always linked before they're needed.
+        // This is the same as extending a border in `border_table`.
+        let mut queue = VecDeque::new();
+        queue.push_back(ROOT);
+        while let Some(node) = queue.pop_front() {
+            let children = nodes[node].children.clone();
+            for (b, child) in children {
+                let fail = if node == ROOT {
+                    ROOT
+                } else {
+                    follow(&nodes, nodes[node].fail, b)
+                };
+                nodes[child].fail = fail;
+                nodes[child].output = if nodes[child].pattern.is_some() {
+                    Some(child)
+                } else {
+                    nodes[fail].output
+                };
+                queue.push_back(child);
+            }
+        }
+
+        AhoCorasick{
+            nodes,
+            pattern_lens: patterns.iter().map(|p| p.len()).collect(),
+            match_kind,
+        }
+    }
+
+    // Finds the leftmost match, choosing between the patterns which
+    // start there by the `MatchKind`, and returns its start offset
+    // and the index of the pattern.
+    pub fn find_first(&self, text: &str) -> Option<(usize, usize)> {
+        // The best match so far, as (start, length, pattern).
+        let mut best: Option<(usize, usize, usize)> = None;
+        if let Some(id) = self.nodes[ROOT].pattern {
+            best = Some((0, 0, id));
+        }
+
+        let mut node = ROOT;
+        for (i, &b) in text.as_bytes().iter().enumerate() {
+            node = self.step(node, b);
+            let end = i + 1;
+
+            // Once no prefix being followed could start at or before
+            // the best match, nothing better can be found.
+            if let Some((start, _, _)) = best {
+                if end - self.nodes[node].depth > start {
+                    break;
+                }
+            }
+
+            // Check every pattern which ends here.
+            let mut output = self.nodes[node].output;
+            while let Some(found) = output {
+                if found == ROOT {
+                    break;
+                }
+                let id = self.nodes[found].pattern.unwrap();
+                let len = self.pattern_lens[id];
+                let start = end - len;
+                let better = match best {
+                    None => true,
+                    Some((best_start, _, _)) if start != best_start => start < best_start,
+                    Some((_, best_len, best_id)) => match self.match_kind {
+                        MatchKind::LeftmostFirst => id < best_id,
+                        MatchKind::LeftmostLongest => len > best_len,
+                    },
+                };
+                if better {
+                    best = Some((start, len, id));
+                }
+                output = self.nodes[self.nodes[found].fail].output;
+            }
+        }
+
+        best.map(|(start, _, id)| (start, id))
+    }
+
+    // Every match of every pattern, including overlapping ones, as
+    // `(start, end, pattern)`, ordered by where they end, then longest
+    // first. Like the other iterators, empty patterns never match.
+    pub fn find_iter<'a>(&'a self, text: &'a str) -> impl Iterator<Item = (usize, usize, usize)> + 'a {
+        AhoCorasickMatches{
+            searcher: self,
+            text: text.as_bytes(),
+            end: 0,
+            node: ROOT,
+            output: None,
+        }
+    }
+
+    fn step(&self, node: usize, b: u8) -> usize {
+        follow(&self.nodes, node, b)
+    }
+}
+
+struct AhoCorasickMatches<'a> {
+    searcher: &'a AhoCorasick,
+    text: &'a [u8],
+    // How much of the text has been read, and the node it led to.
+    end: usize,
+    node: usize,
+    // The next node with a pattern ending at `end`.
+    output: Option<usize>,
+}
+
+impl<'a> Iterator for AhoCorasickMatches<'a> {
+    type Item = (usize, usize, usize);
+
+    fn next(&mut self) -> Option<(usize, usize, usize)> {
+        let nodes = &self.searcher.nodes;
+        loop {
+            // Report the rest of the patterns ending here.
+            if let Some(found) = self.output {
+                if found != ROOT {
+                    self.output = nodes[nodes[found].fail].output;
+                    let id = nodes[found].pattern.unwrap();
+                    let start = self.end - self.searcher.pattern_lens[id];
+                    return Some((start, self.end, id));
+                }
+            }
+
+            // Then read the next byte.
+            let &b = self.text.get(self.end)?;
+            self.node = self.searcher.step(self.node, b);
+            self.end += 1;
+            self.output = nodes[self.node].output;
+        }
+    }
+}
+
+// Follows the edge for `b`, falling back along the failure
+// links until there is one.
+fn follow(nodes: &[Node], mut node: usize, b: u8) -> usize {
+    loop {
+        if let Some(child) = nodes[node].child(b) {
+            return child;
+        }
+        if node == ROOT {
+            return ROOT;
+        }
+        node = nodes[node].fail;
+    }
+}
+
+#[cfg(test)]
+mod correct_return {
+    use std::vec::Vec;
+    use super::{AhoCorasick, MatchKind};
+    use correct_return::{CASES, TEXT};
+
+    #[test]
+    fn single_patterns() {
+        for &(want, pattern) in CASES.iter() {
+            let searcher = AhoCorasick::new(&[pattern]);
+            assert_eq!(searcher.find_first(TEXT), want.map(|start| (start, 0)));
+        }
+    }
+
+    #[test]
+    fn find_first() {
+        let searcher = AhoCorasick::new(&["he", "she", "his", "hers"]);
+        assert_eq!(searcher.find_first("ushers"), Some((1, 1)));
+        assert_eq!(searcher.find_first("ahishers"), Some((1, 2)));
+        assert_eq!(searcher.find_first("the hero"), Some((1, 0)));
+        assert_eq!(searcher.find_first("hersey"), Some((0, 3)));
+        assert_eq!(searcher.find_first("xxhxx"), None);
+        assert_eq!(searcher.find_first(""), None);
+    }
+
+    #[test]
+    fn prefixes_of_each_other() {
+        // The longest of the patterns starting leftmost wins,
+        // wherever it is in the list.
+        let searcher = AhoCorasick::new(&["a", "ab", "abc", "b"]);
+        assert_eq!(searcher.find_first("xabcd"), Some((1, 2)));
+        assert_eq!(searcher.find_first("xabd"), Some((1, 1)));
+        let searcher = AhoCorasick::new(&["abc", "a"]);
+        assert_eq!(searcher.find_first("xabd"), Some((1, 1)));
+        assert_eq!(searcher.find_first("abcabc"), Some((0, 0)));
+    }
+    #[test]
+    fn match_kinds() {
+        let patterns = ["a", "abc"];
+        let first = AhoCorasick::with_match_kind(&patterns, MatchKind::LeftmostFirst);
+        let longest = AhoCorasick::with_match_kind(&patterns, MatchKind::LeftmostLongest);
+        assert_eq!(first.find_first("abcd"), Some((0, 0)));
+        assert_eq!(longest.find_first("abcd"), Some((0, 1)));
+        assert_eq!(AhoCorasick::new(&patterns).find_first("abcd"), longest.find_first("abcd"));
+
+        // Leftmost still comes before either.
+        let first = AhoCorasick::with_match_kind(&["abc", "bcd", "b"], MatchKind::LeftmostFirst);
+        assert_eq!(first.find_first("xbcdabc"), Some((1, 1)));
+        assert_eq!(first.find_first("xabcd"), Some((1, 0)));
+        let first = AhoCorasick::with_match_kind(&["bc", "abc", "", "ab"], MatchKind::LeftmostFirst);
+        assert_eq!(first.find_first("abc"), Some((0, 1)));
+        assert_eq!(first.find_first("xyz"), Some((0, 2)));
+    }
+    #[test]
+    fn find_iter() {
+        let searcher = AhoCorasick::new(&["he", "she", "hers"]);
+        assert_eq!(searcher.find_iter("ushers").collect::<Vec<_>>(),
+                   vec![(1, 4, 1), (2, 4, 0), (2, 6, 2)]);
+        assert_eq!(searcher.find_iter("hehe").collect::<Vec<_>>(), vec![(0, 2, 0), (2, 4, 0)]);
+        assert_eq!(searcher.find_iter("shx").count(), 0);
+
+        // Empty and duplicate patterns.
+        let searcher = AhoCorasick::new(&["", "a", "a", "aa"]);
+        assert_eq!(searcher.find_iter("aaa").collect::<Vec<_>>(),
+                   vec![(0, 1, 1), (0, 2, 3), (1, 2, 1), (1, 3, 3), (2, 3, 1)]);
+        assert_eq!(searcher.find_iter("").count(), 0);
+
+        // The first match agrees with `find_first`'s leftmost longest.
+        for &(want, pattern) in CASES.iter() {
+            let searcher = AhoCorasick::new(&[pattern, "e"]);
+            assert_eq!(searcher.find_iter(TEXT).filter(|m| m.2 == 0).map(|m| m.0).next(),
+                       want.filter(|_| !pattern.is_empty()));
+        }
+    }
+}
@@ -0,0 +1,172 @@
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use super::border_table;
+
+// What a streaming search knows after each chunk of the text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StreamResult {
+    // A match starts here, counting from the start of the stream.
+    Found(usize),
+    // There's no match yet, but this much of the start of the pattern
+    // matches the end of the stream, so the next chunk could finish it.
+    Pending(usize),
+    // There's no match yet, and no part of one at the end of the stream.
+    NotFound,
+}
+
+// KMP as a state machine which is fed the text an element at a time,
+// for embedding in a bigger one, e.g. a parser. The state is how much of
+// the pattern matches the end of what's been fed to it so far.
+#[derive(Clone, Debug)]
+pub struct KmpAutomaton<'s, C: 's> {
+    pattern: &'s [C],
+    borders: Vec<usize>,
+    state: usize,
+    // How many elements have been fed in since the start or a reset.
+    fed: usize,
+}
+
+impl<'s, C> KmpAutomaton<'s, C>
+    where C: PartialEq {
+
+    pub fn new(pattern: &'s [C]) -> KmpAutomaton<'s, C> {
+        KmpAutomaton{
+            pattern,
+            borders: border_table(pattern),
+            state: 0,
+            fed: 0,
+        }
+    }
+
+    // Returns whether a match ends with `c`. After a match, the state
+    // falls back to the pattern's longest border, so overlapping matches
+    // are found too. Like `find_all`, an empty pattern never matches.
+    pub fn step(&mut self, c: C) -> bool {
+        self.step_ref(&c)
+    }
+
+    // `step`, but borrowing the element, so `feed` doesn't need to
+    // clone the chunk's elements.
+    fn step_ref(&mut self, c: &C) -> bool {
+        let (pattern, borders) = (self.pattern, &self.borders);
+        self.fed += 1;
+        if pattern.is_empty() {
+            return false;
+        }
+
+        // Fall back through the borders until one can be
+        // extended by `c`, or none are left.
+        let mut p = self.state;
+        while p > 0 && pattern[p] != *c {
+            p = borders[p - 1];
+        }
+        if pattern[p] == *c {
+            p += 1;
+        }
+
+        let matched = p == pattern.len();
+        self.state = if matched { borders[p - 1] } else { p };
+        matched
+    }
+
+    // Steps through the chunk until a match ends, and says where the
+    // first one started. The rest of the chunk after a match isn't fed
+    // in, so pass it to `feed` again to look for the next match.
+    // Like `str::find`, an empty pattern matches straight away.
+    pub fn feed(&mut self, chunk: &[C]) -> StreamResult {
+        let len = self.pattern.len();
+        if len == 0 {
+            return StreamResult::Found(self.fed);
+        }
+        for c in chunk {
+            if self.step_ref(c) {
+                return StreamResult::Found(self.fed - len);
+            }
+        }
+        match self.state {
+            0 => StreamResult::NotFound,
+            partial => StreamResult::Pending(partial),
+        }
+    }
+
+    pub fn state(&self) -> usize {
+        self.state
+    }
+
+    // Forgets everything fed so far, as if at the start of a new text.
+    pub fn reset(&mut self) {
+        self.state = 0;
+        self.fed = 0;
+    }
+}
+
+#[cfg(test)]
+mod correct_return {
+    use std::vec::Vec;
+    use super::{KmpAutomaton, StreamResult};
+    use correct_return::{CASES, TEXT};
+
+    #[test]
+    fn fires_at_match_ends() {
+        let pattern = "e".chars().collect::<Vec<_>>();
+        let mut automaton = KmpAutomaton::new(&pattern[..]);
+        let ends = TEXT.chars().enumerate()
+            .filter(|&(_, c)| automaton.step(c))
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+        assert_eq!(ends, vec![2, 12, 17, 23]);
+
+        for &(want, pattern) in CASES.iter() {
+            if pattern.is_empty() {
+                continue;
+            }
+            let chars = pattern.chars().collect::<Vec<_>>();
+            let mut automaton = KmpAutomaton::new(&chars[..]);
+            let first_end = TEXT.chars().position(|c| automaton.step(c));
+            assert_eq!(first_end, want.map(|start| start + chars.len() - 1), "{:?}", pattern);
+        }
+    }
+
+    #[test]
+    fn state_and_reset() {
+        let pattern = b"abab";
+        let mut automaton = KmpAutomaton::new(&pattern[..]);
+        let fired = b"abababxab".iter().map(|&b| automaton.step(b)).collect::<Vec<_>>();
+        assert_eq!(fired, vec![false, false, false, true, false, true, false, false, false]);
+        // "ab" of the pattern matches the end of the input.
+        assert_eq!(automaton.state(), 2);
+        automaton.reset();
+        assert_eq!(automaton.state(), 0);
+        assert!(!automaton.step(b'a') && !automaton.step(b'b'));
+
+        let mut automaton = KmpAutomaton::new(&b""[..]);
+        assert!(!automaton.step(b'a'));
+    }
+
+    #[test]
+    fn feed() {
+        let pattern = b"dead";
+        let mut automaton = KmpAutomaton::new(&pattern[..]);
+        let (first, second) = TEXT.as_bytes().split_at(18);
+        assert_eq!(automaton.feed(&first[..10]), StreamResult::NotFound);
+        // "de" is at the end of the first chunk, and "ad" is in the next.
+        assert_eq!(automaton.feed(&first[10..]), StreamResult::Pending(2));
+        assert_eq!(automaton.feed(second), StreamResult::Found(16));
+        // The rest of the text has no more matches.
+        assert_eq!(automaton.feed(&second[2..]), StreamResult::NotFound);
+
+        // An element at a time, with overlapping matches.
+        let pattern = b"aa";
+        let mut automaton = KmpAutomaton::new(&pattern[..]);
+        let results = b"baaa".chunks(1).map(|c| automaton.feed(c)).collect::<Vec<_>>();
+        assert_eq!(results, vec![StreamResult::NotFound, StreamResult::Pending(1),
+                                 StreamResult::Found(1), StreamResult::Found(2)]);
+        automaton.reset();
+        assert_eq!(automaton.feed(b"aa"), StreamResult::Found(0));
+
+        let mut automaton = KmpAutomaton::new(&b""[..]);
+        assert_eq!(automaton.feed(b"abc"), StreamResult::Found(0));
+        assert_eq!(automaton.feed(b""), StreamResult::Found(0));
+    }
+}
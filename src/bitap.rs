@@ -0,0 +1,652 @@
+use super::{border_table, kmp_search, bad_character_table_bytes, bmh_search_bytes};
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use core::cmp;
+
+// The longest pattern whose state fits in a machine word.
+pub const MAX_PATTERN_LEN: usize = 64;
+
+// Searches for the pattern with the Shift-Or algorithm, keeping one bit
+// of state per pattern byte. Patterns longer than `MAX_PATTERN_LEN`
+// don't fit in the state, so they're searched for with KMP instead.
+pub fn shift_or(pattern: &[u8], text: &[u8]) -> Option<usize> {
+    // Like `str::find`, an empty pattern matches at the start.
+    if pattern.is_empty() {
+        return Some(0);
+    }
+    if pattern.len() > MAX_PATTERN_LEN {
+        return kmp_search(pattern, text, &border_table(pattern));
+    }
+
+    let masks = shift_or_masks(pattern);
+    let last = 1 << (pattern.len() - 1);
+
+    // Bit `i` of the state is clear when the last `i + 1` bytes
+    // of the text match the first `i + 1` bytes of the pattern.
+    let mut state = !0u64;
+    for (t, &c) in text.iter().enumerate() {
+        state = (state << 1) | masks[c as usize];
+        // If the whole pattern matches, it started `len - 1` bytes ago.
+        if state & last == 0 {
+            return Some(t + 1 - pattern.len());
+        }
+    }
+    None
+}
+
+// Shift-Or with the bits the other way around: a set bit is a match.
+// That takes an extra AND per byte of text, but each byte's mask is
+// just the set of pattern positions it can be at, so a position
+// can accept more than one byte by being in more than one mask.
+pub fn shift_and(pattern: &[u8], text: &[u8]) -> Option<usize> {
+    // Like `str::find`, an empty pattern matches at the start.
+    if pattern.is_empty() {
+        return Some(0);
+    }
+    if pattern.len() > MAX_PATTERN_LEN {
+        return kmp_search(pattern, text, &border_table(pattern));
+    }
+
+    let mut masks = vec![0u64; 256];
+    for (i, &c) in pattern.iter().enumerate() {
+        masks[c as usize] |= 1 << i;
+    }
+    shift_and_search(&masks, pattern.len(), text)
+}
+
+// Finds the first match of a pattern of `len` positions, where bit `i`
+// of `masks[c]` is set when `c` is accepted at position `i`.
+fn shift_and_search(masks: &[u64], len: usize, text: &[u8]) -> Option<usize> {
+    let last = 1 << (len - 1);
+
+    // Bit `i` of the state is set when the last `i + 1` bytes
+    // of the text match the first `i + 1` positions of the pattern.
+    let mut state = 0u64;
+    for (t, &c) in text.iter().enumerate() {
+        state = ((state << 1) | 1) & masks[c as usize];
+        // If the whole pattern matches, it started `len - 1` bytes ago.
+        if state & last != 0 {
+            return Some(t + 1 - len);
+        }
+    }
+    None
+}
+
+// A pattern where each position accepts a set of bytes, like `[aeiou]`
+// in a regex, searched for with Shift-And. Bytes in more than one
+// position's set have more than one bit set in their mask.
+#[derive(Clone, Debug)]
+pub struct ClassPattern {
+    masks: Vec<u64>,
+    len: usize,
+}
+
+impl ClassPattern {
+    // Panics if there are more than `MAX_PATTERN_LEN` positions.
+    // A position with an empty set never matches anything.
+    pub fn new(classes: &[&[u8]]) -> ClassPattern {
+        assert!(classes.len() <= MAX_PATTERN_LEN,
+                "a class pattern can have at most {} positions", MAX_PATTERN_LEN);
+        let mut masks = vec![0u64; 256];
+        for (i, class) in classes.iter().enumerate() {
+            for &c in class.iter() {
+                masks[c as usize] |= 1 << i;
+            }
+        }
+        ClassPattern{
+            masks,
+            len: classes.len(),
+        }
+    }
+
+    pub fn find(&self, text: &[u8]) -> Option<usize> {
+        // Like `str::find`, an empty pattern matches at the start.
+        if self.len == 0 {
+            return Some(0);
+        }
+        shift_and_search(&self.masks, self.len, text)
+    }
+}
+
+// Finds the first place where the pattern matches the text with
+// at most `k` bytes substituted, i.e. within a Hamming distance of `k`.
+// This only allows substitutions, not insertions or deletions.
+// The bit parallel search only fits patterns of up to `MAX_PATTERN_LEN`
+// bytes, so longer patterns are compared against each window in turn.
+pub fn bitap_fuzzy(pattern: &[u8], text: &[u8], k: usize) -> Option<usize> {
+    // Like `str::find`, an empty pattern matches at the start.
+    if pattern.is_empty() {
+        return Some(0);
+    }
+    if pattern.len() > MAX_PATTERN_LEN {
+        return hamming_search(pattern, text, k);
+    }
+
+    // More mismatches than the pattern has bytes never helps.
+    let k = core::cmp::min(k, pattern.len());
+    let masks = shift_or_masks(pattern);
+    let last = 1 << (pattern.len() - 1);
+
+    // Bit `i` of `states[j]` is clear when the last `i + 1` bytes
+    // of the text match the first `i + 1` bytes of the pattern
+    // with at most `j` mismatches.
+    let mut states = vec![!0u64; k + 1];
+    for (t, &c) in text.iter().enumerate() {
+        // Either the prefix matched with `j` mismatches and this byte
+        // matches, or it matched with `j - 1` and this byte doesn't.
+        let mut fewer = states[0];
+        states[0] = (states[0] << 1) | masks[c as usize];
+        for state in states.iter_mut().skip(1) {
+            let previous = *state;
+            *state = ((previous << 1) | masks[c as usize]) & (fewer << 1);
+            fewer = previous;
+        }
+        if states[k] & last == 0 {
+            return Some(t + 1 - pattern.len());
+        }
+    }
+    None
+}
+
+// Finds the first place where the pattern matches the text within
+// a Levenshtein distance of `k`, i.e. with at most `k` bytes inserted,
+// deleted or substituted. The match is the one which ends first, and
+// of the windows ending there, the one closest to the pattern,
+// then the longest. The bit parallel search, Wu and Manber's extension
+// of Shift-Or, only fits patterns of up to `MAX_PATTERN_LEN` bytes,
+// so longer patterns fall back to a dynamic programming scan, which
+// takes time proportional to the pattern's length for each byte.
+pub fn fuzzy_edit_search(pattern: &[u8], text: &[u8], k: usize) -> Option<usize> {
+    let m = pattern.len();
+    // Deleting the whole pattern matches the start of anything.
+    if k >= m {
+        return Some(0);
+    }
+    let end = if m > MAX_PATTERN_LEN {
+        edit_distance_end(pattern, text, k)
+    } else {
+        wu_manber_end(pattern, text, k)
+    }?;
+    Some(closest_start(pattern, &text[..end], k))
+}
+
+// Where the first match within `k` edits ends, for `k < pattern.len()`.
+fn wu_manber_end(pattern: &[u8], text: &[u8], k: usize) -> Option<usize> {
+    // Unlike `bitap_fuzzy`, bit `i` of `states[j]` is set when the first
+    // `i + 1` bytes of the pattern match the end of the text so far
+    // with at most `j` edits, so that deleting the start of the pattern
+    // can be done by setting bits.
+    let mut masks = vec![0u64; 256];
+    for (i, &c) in pattern.iter().enumerate() {
+        masks[c as usize] |= 1 << i;
+    }
+    let last = 1 << (pattern.len() - 1);
+
+    // Before any text, `j` edits can delete the first `j` bytes.
+    let mut states = (0..k + 1).map(|j| (1u64 << j) - 1).collect::<Vec<_>>();
+    for (t, &c) in text.iter().enumerate() {
+        let mask = masks[c as usize];
+        // `fewer` and `fewer_next` are `states[j - 1]` before and
+        // after this byte.
+        let mut fewer = states[0];
+        states[0] = ((states[0] << 1) | 1) & mask;
+        let mut fewer_next = states[0];
+        for state in states.iter_mut().skip(1) {
+            let previous = *state;
+            // Either this byte matches, or it was inserted, or it
+            // replaced a pattern byte, or a pattern byte was deleted.
+            *state = (((previous << 1) | 1) & mask)
+                   | fewer
+                   | (fewer << 1) | 1
+                   | (fewer_next << 1);
+            fewer = previous;
+            fewer_next = *state;
+        }
+        if states[k] & last != 0 {
+            return Some(t + 1);
+        }
+    }
+    None
+}
+
+// `wu_manber_end` by Sellers' algorithm: `distances[i]` is the least
+// number of edits between the first `i` bytes of the pattern and
+// any suffix of the text so far.
+fn edit_distance_end(pattern: &[u8], text: &[u8], k: usize) -> Option<usize> {
+    let mut distances = (0..pattern.len() + 1).collect::<Vec<_>>();
+    for (t, &c) in text.iter().enumerate() {
+        let mut diagonal = distances[0];
+        for (i, &p) in pattern.iter().enumerate() {
+            let next = cmp::min(diagonal + (p != c) as usize,
+                                cmp::min(distances[i + 1], distances[i]) + 1);
+            diagonal = distances[i + 1];
+            distances[i + 1] = next;
+        }
+        if distances[pattern.len()] <= k {
+            return Some(t + 1);
+        }
+    }
+    None
+}
+
+// The start of the suffix of the text with the fewest edits from the
+// pattern, preferring longer ones, given that one is within `k`.
+fn closest_start(pattern: &[u8], text: &[u8], k: usize) -> usize {
+    // A match needs at most `k` bytes more than the pattern.
+    let window = &text[text.len().saturating_sub(pattern.len() + k)..];
+
+    // `distances[i]` is the number of edits between the last `i` bytes
+    // of the pattern and the last `len` bytes of the window.
+    let mut distances = (0..pattern.len() + 1).collect::<Vec<_>>();
+    let mut best = (distances[pattern.len()], 0);
+    for (len, &c) in window.iter().rev().enumerate() {
+        let mut diagonal = distances[0];
+        distances[0] = len + 1;
+        for (i, &p) in pattern.iter().rev().enumerate() {
+            let next = cmp::min(diagonal + (p != c) as usize,
+                                cmp::min(distances[i + 1], distances[i]) + 1);
+            diagonal = distances[i + 1];
+            distances[i + 1] = next;
+        }
+        if distances[pattern.len()] <= best.0 {
+            best = (distances[pattern.len()], len + 1);
+        }
+    }
+    text.len() - best.1
+}
+
+// Searches for the pattern with Backward Nondeterministic DAWG Matching,
+// which reads each window backwards while tracking, one bit per pattern
+// byte, every place in the pattern that what's been read so far occurs.
+// Like `shift_or`, this only works for patterns of up to
+// `MAX_PATTERN_LEN` bytes; longer ones are searched for with BMH,
+// which is the better choice for them anyway.
+pub fn bndm(pattern: &[u8], text: &[u8]) -> Option<usize> {
+    // Like `str::find`, an empty pattern matches at the start.
+    if pattern.is_empty() {
+        return Some(0);
+    }
+    let m = pattern.len();
+    if m > MAX_PATTERN_LEN {
+        return bmh_search_bytes(pattern, text, &bad_character_table_bytes(pattern));
+    }
+
+    // `masks[c]` has bit `m - 1 - i` set when `pattern[i]` is `c`,
+    // so the pattern's bits are in reverse.
+    let mut masks = vec![0u64; 256];
+    for (i, &c) in pattern.iter().enumerate() {
+        masks[c as usize] |= 1 << (m - 1 - i);
+    }
+    let first = 1 << (m - 1);
+
+    let mut t = 0;
+    while t + m <= text.len() {
+        // `j` is how much of the window is left to read, and `shift`
+        // is where the last prefix of the pattern seen in it started.
+        let (mut j, mut shift) = (m, m);
+        let mut state = !0u64;
+        // Once what's been read isn't in the pattern at all,
+        // no window overlapping it can match.
+        while state != 0 {
+            state &= masks[text[t + j - 1] as usize];
+            j -= 1;
+            // What's been read is a prefix of the pattern.
+            if state & first != 0 {
+                if j == 0 {
+                    return Some(t);
+                }
+                shift = j;
+            }
+            state <<= 1;
+        }
+        t += shift;
+    }
+    None
+}
+
+// Finds the pattern, where a `None` in the pattern matches any byte,
+// like `?` in a glob. Patterns up to `MAX_PATTERN_LEN` long are
+// searched for with Shift-Or, with the wildcards' bits clear in every
+// mask, and longer ones by comparing them against each window.
+pub fn wildcard_search(pattern: &[Option<u8>], text: &[u8]) -> Option<usize> {
+    // Like `str::find`, an empty pattern matches at the start.
+    if pattern.is_empty() {
+        return Some(0);
+    }
+    if pattern.len() > MAX_PATTERN_LEN {
+        if pattern.len() > text.len() {
+            return None;
+        }
+        return (0..text.len() - pattern.len() + 1).find(|&t| {
+            text[t..t + pattern.len()].iter().zip(pattern).all(|(&c, p)| p.is_none_or(|p| p == c))
+        });
+    }
+
+    let mut masks = vec![!0u64; 256];
+    for (i, p) in pattern.iter().enumerate() {
+        match *p {
+            Some(c) => masks[c as usize] &= !(1 << i),
+            None => for mask in masks.iter_mut() {
+                *mask &= !(1 << i);
+            },
+        }
+    }
+    let last = 1 << (pattern.len() - 1);
+
+    // The same as `shift_or`.
+    let mut state = !0u64;
+    for (t, &c) in text.iter().enumerate() {
+        state = (state << 1) | masks[c as usize];
+        if state & last == 0 {
+            return Some(t + 1 - pattern.len());
+        }
+    }
+    None
+}
+
+// Compares the pattern against every window of the text.
+fn hamming_search(pattern: &[u8], text: &[u8], k: usize) -> Option<usize> {
+    if pattern.len() > text.len() {
+        return None;
+    }
+    (0..text.len() - pattern.len() + 1).find(|&t| {
+        let window = &text[t..t + pattern.len()];
+        window.iter().zip(pattern).filter(|&(a, b)| a != b).count() <= k
+    })
+}
+
+// `masks[c]` has bit `i` clear when `pattern[i]` is `c`.
+fn shift_or_masks(pattern: &[u8]) -> Vec<u64> {
+    let mut masks = vec![!0u64; 256];
+    for (i, &c) in pattern.iter().enumerate() {
+        masks[c as usize] &= !(1 << i);
+    }
+    masks
+}
+
+#[cfg(test)]
+mod correct_return {
+    use std::vec::Vec;
+    use super::{shift_or, shift_and, ClassPattern, bitap_fuzzy, bndm, wildcard_search, hamming_search, MAX_PATTERN_LEN};
+    use super::{fuzzy_edit_search, wu_manber_end, edit_distance_end};
+    use linear_search;
+    use correct_return::{CASES, TEXT, Random};
+
+    #[test]
+    fn shift_or_fixtures() {
+        for &(want, pattern) in CASES.iter() {
+            assert_eq!(shift_or(pattern.as_bytes(), TEXT.as_bytes()), want);
+        }
+        assert_eq!(shift_or(b"", b""), Some(0));
+        assert_eq!(shift_or(b"\x00\xff", b"\xff\x00\xff\x00"), Some(1));
+    }
+
+    #[test]
+    fn shift_or_long_patterns() {
+        let text = "ab".repeat(100);
+        let fits = &text.as_bytes()[1..1 + MAX_PATTERN_LEN];
+        let too_long = &text.as_bytes()[1..2 + MAX_PATTERN_LEN];
+        assert_eq!(shift_or(fits, text.as_bytes()), Some(1));
+        assert_eq!(shift_or(too_long, text.as_bytes()), Some(1));
+        assert_eq!(shift_or(too_long, &text.as_bytes()[2..60]), None);
+    }
+
+    #[test]
+    fn shift_or_agrees_with_linear() {
+        let mut random = Random(0x2545f4914f6cdd1d);
+        for _ in 0..10000 {
+            let pattern = random.bytes(6, 3);
+            let text = random.bytes(20, 3);
+            assert_eq!(shift_or(&pattern, &text), linear_search(&pattern, &text),
+                       "{:?} in {:?}", pattern, text);
+        }
+    }
+
+    #[test]
+    fn shift_and_fixtures() {
+        for &(want, pattern) in CASES.iter() {
+            assert_eq!(shift_and(pattern.as_bytes(), TEXT.as_bytes()), want);
+        }
+        assert_eq!(shift_and(b"", b""), Some(0));
+        assert_eq!(shift_and(b"\x00\xff", b"\xff\x00\xff\x00"), Some(1));
+
+        let text = "ab".repeat(100);
+        let fits = &text.as_bytes()[1..1 + MAX_PATTERN_LEN];
+        let too_long = &text.as_bytes()[1..2 + MAX_PATTERN_LEN];
+        assert_eq!(shift_and(fits, text.as_bytes()), Some(1));
+        assert_eq!(shift_and(too_long, text.as_bytes()), Some(1));
+        assert_eq!(shift_and(fits, &text.as_bytes()[2..60]), None);
+    }
+
+    #[test]
+    fn shift_and_agrees_with_shift_or() {
+        let mut random = Random(0x5851f42d4c957f2d);
+        for _ in 0..10000 {
+            let pattern = random.bytes(6, 3);
+            let text = random.bytes(20, 3);
+            let want = linear_search(&pattern, &text);
+            assert_eq!(shift_and(&pattern, &text), want, "{:?} in {:?}", pattern, text);
+            assert_eq!(shift_or(&pattern, &text), want, "{:?} in {:?}", pattern, text);
+        }
+    }
+
+    #[test]
+    fn class_pattern() {
+        let classes: [&[u8]; 3] = [b"d", b"o0", b"g"];
+        let pattern = ClassPattern::new(&classes);
+        assert_eq!(pattern.find(b"a dog"), Some(2));
+        assert_eq!(pattern.find(b"a d0g"), Some(2));
+        assert_eq!(pattern.find(b"a dig, a d0g"), Some(9));
+        assert_eq!(pattern.find(b"a dOg"), None);
+        assert_eq!(pattern.find(b"do"), None);
+
+        // "a" is in two of the classes, so it has two bits in its mask.
+        let classes: [&[u8]; 3] = [b"dt", b"aeiou", b"ad"];
+        assert_eq!(ClassPattern::new(&classes).find(TEXT.as_bytes()), Some(16));
+        let classes: [&[u8]; 2] = [b"", b"a"];
+        assert_eq!(ClassPattern::new(&classes).find(b"aaa"), None);
+        assert_eq!(ClassPattern::new(&[]).find(b""), Some(0));
+    }
+
+    #[test]
+    fn class_pattern_agrees_with_linear() {
+        // With one byte in each class, it's an ordinary pattern.
+        let mut random = Random(0x9e3779b97f4a7c15);
+        for _ in 0..10000 {
+            let pattern = random.bytes(6, 3);
+            let text = random.bytes(20, 3);
+            let classes = pattern.chunks(1).collect::<Vec<_>>();
+            assert_eq!(ClassPattern::new(&classes).find(&text), linear_search(&pattern, &text),
+                       "{:?} in {:?}", pattern, text);
+        }
+        let text = "ab".repeat(100);
+        let classes = text.as_bytes()[1..1 + MAX_PATTERN_LEN].chunks(1).collect::<Vec<_>>();
+        assert_eq!(ClassPattern::new(&classes).find(text.as_bytes()), Some(1));
+    }
+
+    #[test]
+    #[should_panic]
+    fn class_pattern_too_long() {
+        let classes = vec![&b"a"[..]; MAX_PATTERN_LEN + 1];
+        ClassPattern::new(&classes);
+    }
+
+    #[test]
+    fn bitap_fuzzy_substitutions() {
+        let text = b"the dog is very dead";
+        assert_eq!(bitap_fuzzy(b"dug", text, 1), Some(4));
+        assert_eq!(bitap_fuzzy(b"dug", text, 0), None);
+        assert_eq!(bitap_fuzzy(b"dxxd", text, 2), Some(16));
+        assert_eq!(bitap_fuzzy(b"dxxd", text, 1), None);
+        // Everything is within the pattern's length of the first window.
+        assert_eq!(bitap_fuzzy(b"xyz", text, 5), Some(0));
+        assert_eq!(bitap_fuzzy(b"xyz", b"xy", 5), None);
+    }
+
+    #[test]
+    fn bitap_fuzzy_exact() {
+        for &(want, pattern) in CASES.iter() {
+            assert_eq!(bitap_fuzzy(pattern.as_bytes(), TEXT.as_bytes(), 0), want);
+        }
+    }
+
+    #[test]
+    fn bitap_fuzzy_agrees_with_windows() {
+        let mut random = Random(0x9e3779b97f4a7c15);
+        for _ in 0..10000 {
+            let pattern = random.bytes(6, 3);
+            let text = random.bytes(20, 3);
+            let k = random.below(3) as usize;
+            assert_eq!(bitap_fuzzy(&pattern, &text, k), hamming_search(&pattern, &text, k),
+                       "{:?} in {:?} with {}", pattern, text, k);
+        }
+
+        // Long patterns use the window comparison directly.
+        let text = "ab".repeat(100).into_bytes();
+        let mut pattern = text[..MAX_PATTERN_LEN + 1].to_vec();
+        pattern[3] = b'x';
+        assert_eq!(bitap_fuzzy(&pattern, &text, 1), Some(0));
+        assert_eq!(bitap_fuzzy(&pattern, &text, 0), None);
+    }
+
+    #[test]
+    fn bndm_fixtures() {
+        for &(want, pattern) in CASES.iter() {
+            assert_eq!(bndm(pattern.as_bytes(), TEXT.as_bytes()), want);
+        }
+        assert_eq!(bndm(b"", b""), Some(0));
+        assert_eq!(bndm(b"ab", b"a"), None);
+        assert_eq!(bndm(b"\x00\xff", b"\xff\x00\xff\x00"), Some(1));
+    }
+
+    #[test]
+    fn bndm_long_patterns() {
+        // Exactly `MAX_PATTERN_LEN` bytes uses every bit of the state,
+        // and one more falls back to BMH.
+        let text = "abc".repeat(100);
+        for len in MAX_PATTERN_LEN - 1..MAX_PATTERN_LEN + 2 {
+            let pattern = &text.as_bytes()[2..2 + len];
+            assert_eq!(bndm(pattern, text.as_bytes()), Some(2));
+            assert_eq!(bndm(pattern, &text.as_bytes()[3..]), Some(2));
+            assert_eq!(bndm(pattern, &text.as_bytes()[3..len + 4]), None);
+        }
+    }
+
+    #[test]
+    fn bndm_agrees_with_linear() {
+        let mut random = Random(0x2545f4914f6cdd1d);
+        for _ in 0..10000 {
+            let pattern = random.bytes(6, 3);
+            let text = random.bytes(20, 3);
+            assert_eq!(bndm(&pattern, &text), linear_search(&pattern, &text),
+                       "{:?} in {:?}", pattern, text);
+        }
+    }
+    #[test]
+    fn wildcard_fixtures() {
+        assert_eq!(wildcard_search(&[Some(b'd'), None, Some(b'g')], TEXT.as_bytes()), Some(4));
+        assert_eq!(wildcard_search(&[Some(b'd'), None, None, Some(b'd')], TEXT.as_bytes()), Some(16));
+        assert_eq!(wildcard_search(&[None, Some(b'h')], TEXT.as_bytes()), Some(0));
+        assert_eq!(wildcard_search(&[None; 3], b"ab"), None);
+        assert_eq!(wildcard_search(&[None; 3], b"abc"), Some(0));
+        assert_eq!(wildcard_search(&[], b""), Some(0));
+        for &(want, pattern) in CASES.iter() {
+            let pattern = pattern.bytes().map(Some).collect::<Vec<_>>();
+            assert_eq!(wildcard_search(&pattern, TEXT.as_bytes()), want);
+        }
+    }
+
+    #[test]
+    fn wildcard_long_patterns() {
+        let text = "abc".repeat(100).into_bytes();
+        let mut pattern = text[1..MAX_PATTERN_LEN + 2].iter().cloned().map(Some).collect::<Vec<_>>();
+        pattern[0] = None;
+        pattern[5] = None;
+        assert_eq!(wildcard_search(&pattern, &text), Some(1));
+        assert_eq!(wildcard_search(&pattern[..MAX_PATTERN_LEN], &text), Some(1));
+        pattern[6] = Some(b'x');
+        assert_eq!(wildcard_search(&pattern, &text), None);
+        assert_eq!(wildcard_search(&pattern, &text[..10]), None);
+    }
+
+    #[test]
+    fn wildcard_agrees_with_linear() {
+        // Without wildcards, it's an exact search.
+        let mut random = Random(0x5851f42d4c957f2d);
+        for _ in 0..10000 {
+            let pattern = random.bytes(6, 3);
+            let text = random.bytes(20, 3);
+            let wildcards = pattern.iter().cloned().map(Some).collect::<Vec<_>>();
+            assert_eq!(wildcard_search(&wildcards, &text), linear_search(&pattern, &text),
+                       "{:?} in {:?}", pattern, text);
+        }
+    }
+    // The Levenshtein distance between `a` and `b`.
+    fn edit_distance(a: &[u8], b: &[u8]) -> usize {
+        let mut distances = (0..b.len() + 1).collect::<Vec<_>>();
+        for (i, &x) in a.iter().enumerate() {
+            let mut diagonal = distances[0];
+            distances[0] = i + 1;
+            for (j, &y) in b.iter().enumerate() {
+                let next = (diagonal + (x != y) as usize).min(distances[j] + 1).min(distances[j + 1] + 1);
+                diagonal = distances[j + 1];
+                distances[j + 1] = next;
+            }
+        }
+        distances[b.len()]
+    }
+
+    // `fuzzy_edit_search` by trying every window.
+    fn edit_windows(pattern: &[u8], text: &[u8], k: usize) -> Option<usize> {
+        (0..text.len() + 1).filter_map(|end| {
+            (0..end + 1).map(|start| (edit_distance(pattern, &text[start..end]), start))
+                        .filter(|&(distance, _)| distance <= k)
+                        .min()
+                        .map(|(_, start)| start)
+        }).next()
+    }
+
+    #[test]
+    fn fuzzy_edit_fixtures() {
+        let text = TEXT.as_bytes();
+        // One insertion.
+        assert_eq!(fuzzy_edit_search(b"dg", text, 1), Some(4));
+        // One deletion, and one of each.
+        assert_eq!(fuzzy_edit_search(b"doog", text, 1), Some(4));
+        // Deleting "y" gives "ver", before the transposition in "very".
+        assert_eq!(fuzzy_edit_search(b"veyr", text, 1), Some(11));
+        assert_eq!(fuzzy_edit_search(b"cat", text, 1), None);
+        assert_eq!(fuzzy_edit_search(b"xyz", b"ab", 3), Some(0));
+        assert_eq!(fuzzy_edit_search(b"xyz", b"", 2), None);
+        for &(want, pattern) in CASES.iter() {
+            assert_eq!(fuzzy_edit_search(pattern.as_bytes(), text, 0), want);
+        }
+    }
+
+    #[test]
+    fn fuzzy_edit_agrees_with_windows() {
+        let mut random = Random(0xd1b54a32d192ed03);
+        for _ in 0..5000 {
+            let pattern = random.bytes(6, 3);
+            let text = random.bytes(15, 3);
+            let k = random.below(3) as usize;
+            assert_eq!(fuzzy_edit_search(&pattern, &text, k), edit_windows(&pattern, &text, k),
+                       "{:?} in {:?} with {}", pattern, text, k);
+            if k < pattern.len() {
+                assert_eq!(edit_distance_end(&pattern, &text, k), wu_manber_end(&pattern, &text, k));
+            }
+        }
+    }
+
+    #[test]
+    fn fuzzy_edit_long_patterns() {
+        let text = "abc".repeat(100).into_bytes();
+        let mut pattern = text[..MAX_PATTERN_LEN + 3].to_vec();
+        pattern.remove(10);
+        pattern.insert(20, b'x');
+        assert_eq!(fuzzy_edit_search(&pattern, &text, 2), Some(0));
+        assert_eq!(fuzzy_edit_search(&pattern, &text, 1), None);
+        assert_eq!(fuzzy_edit_search(&pattern, &text[1..], 2), Some(2));
+    }
+}
@@ -0,0 +1,122 @@
+use std::cmp;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use super::Searcher;
+
+// BMH over any elements which can be hashed, e.g. chars or tokens.
+// The bad character table is a `HashMap` rather than an array indexed
+// by byte, so each shift costs a hash lookup, and `BMHBytesPattern`
+// is faster whenever the elements are bytes. `HashMap` needs `std`,
+// so this does too.
+#[derive(Clone, Debug)]
+pub struct BMHGeneric<'s, C: 's> {
+    pattern: &'s [C],
+    bad_char_table: Option<HashMap<&'s C, usize>>,
+}
+
+impl<'s, C> BMHGeneric<'s, C>
+    where C: Eq + Hash {
+
+    pub fn new(pattern: &'s [C]) -> BMHGeneric<'s, C> {
+        BMHGeneric{
+            pattern,
+            bad_char_table: None,
+        }
+    }
+
+    pub fn bmh(&mut self, text: &[C]) -> Option<usize> {
+        let pattern = self.pattern;
+        // Like `str::find`, an empty pattern matches at the start.
+        if pattern.is_empty() {
+            return Some(0);
+        }
+
+        // Generate the bad character table using the pattern.
+        // Elements which aren't in it can shift past the whole pattern.
+        if self.bad_char_table.is_none() {
+            let mut table = HashMap::new();
+            for (i, c) in pattern.iter().enumerate() {
+                table.insert(c, pattern.len() - 1 - i);
+            }
+            self.bad_char_table = Some(table);
+        }
+        let bad_char_table = self.bad_char_table.as_ref().unwrap();
+
+        // Search as in `bmh_search_by`.
+        let mut t = 0;
+        while t + pattern.len() <= text.len() {
+            let mut p = pattern.len() - 1;
+            while text[t+p] == pattern[p] {
+                if p == 0 {
+                    return Some(t)
+                }
+                p -= 1;
+            }
+            let shift = bad_char_table.get(&text[t+p]).cloned().unwrap_or(pattern.len());
+            t += cmp::max(shift.saturating_sub(pattern.len() - 1 - p), 1);
+        }
+        None
+    }
+}
+
+impl<'s, C> Searcher<C> for BMHGeneric<'s, C>
+    where C: Eq + Hash {
+
+    fn find(&mut self, text: &[C]) -> Option<usize> {
+        self.bmh(text)
+    }
+}
+
+#[cfg(test)]
+mod correct_return {
+    use super::BMHGeneric;
+    use linear_search;
+    use correct_return::{CASES, TEXT, Random};
+
+    #[test]
+    fn chars() {
+        let text = TEXT.chars().collect::<Vec<_>>();
+        for &(want, pattern) in CASES.iter() {
+            let chars = pattern.chars().collect::<Vec<_>>();
+            let mut searcher = BMHGeneric::new(&chars[..]);
+            assert_eq!(searcher.bmh(&text[..]), want);
+        }
+        let text = "日本語のテキスト".chars().collect::<Vec<_>>();
+        let pattern = "テキ".chars().collect::<Vec<_>>();
+        assert_eq!(BMHGeneric::new(&pattern[..]).bmh(&text[..]), Some(4));
+    }
+
+    #[derive(Debug, PartialEq, Eq, Hash)]
+    enum Token {
+        Let,
+        Ident(&'static str),
+        Equals,
+        Number(u32),
+        Semicolon,
+    }
+
+    #[test]
+    fn tokens() {
+        use self::Token::*;
+        let text = [Let, Ident("x"), Equals, Number(1), Semicolon,
+                    Let, Ident("y"), Equals, Number(2), Semicolon];
+        let mut searcher = BMHGeneric::new(&[Ident("y"), Equals]);
+        assert_eq!(searcher.bmh(&text), Some(6));
+        let mut searcher = BMHGeneric::new(&[Equals, Number(2)]);
+        assert_eq!(searcher.bmh(&text), Some(7));
+        let mut searcher = BMHGeneric::new(&[Number(1), Let]);
+        assert_eq!(searcher.bmh(&text), None);
+    }
+
+    #[test]
+    fn agrees_with_linear() {
+        let mut random = Random(0x2545f4914f6cdd1d);
+        for _ in 0..10000 {
+            let pattern = random.bytes(6, 3).into_iter().map(u32::from).collect::<Vec<_>>();
+            let text = random.bytes(20, 3).into_iter().map(u32::from).collect::<Vec<_>>();
+            assert_eq!(BMHGeneric::new(&pattern[..]).bmh(&text), linear_search(&pattern, &text),
+                       "{:?} in {:?}", pattern, text);
+        }
+    }
+}
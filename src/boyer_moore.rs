@@ -0,0 +1,333 @@
+use super::{bad_character_table, bad_character_shift};
+use super::z_algorithm::z_array;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use core::cmp;
+
+#[derive(Clone, Debug)]
+pub struct BoyerMoorePattern<'s> {
+    pattern: &'s str,
+    bad_char_table: Option<Vec<usize>>,
+    good_suffix_table: Option<Vec<usize>>,
+    suffix_table: Option<Vec<usize>>,
+}
+
+impl<'s> BoyerMoorePattern<'s> {
+    pub fn new(pattern: &'s str) -> BoyerMoorePattern<'s> {
+        BoyerMoorePattern{
+            pattern,
+            bad_char_table: None,
+            good_suffix_table: None,
+            suffix_table: None,
+        }
+    }
+
+    pub fn bm(&mut self, text: &str) -> Option<usize> {
+        let pattern = self.pattern;
+        self.build_tables();
+        let bad_char_table = self.bad_char_table.as_ref().unwrap();
+        let good_suffix_table = self.good_suffix_table.as_ref().unwrap();
+
+        // Search the text using the pattern and both tables.
+        bm_search(pattern, text, bad_char_table, good_suffix_table)
+    }
+
+    // Boyer-Moore with the Apostolico-Giancarlo rule, which remembers
+    // how much of the pattern matched the text ending at each place in
+    // the window, so those parts aren't compared again after a shift.
+    // That makes it linear in the length of the text even at worst,
+    // at the cost of another table and a pattern length of memory
+    // for each search.
+    pub fn ag(&mut self, text: &str) -> Option<usize> {
+        let pattern = self.pattern;
+        self.build_tables();
+        if self.suffix_table.is_none() {
+            self.suffix_table = Some(suffix_table(pattern.as_bytes()));
+        }
+        let bad_char_table = self.bad_char_table.as_ref().unwrap();
+        let good_suffix_table = self.good_suffix_table.as_ref().unwrap();
+        let suffix_table = self.suffix_table.as_ref().unwrap();
+
+        ag_search_by(pattern.as_bytes(), text.as_bytes(),
+                     bad_char_table, good_suffix_table, suffix_table, |a, b| a == b)
+    }
+
+    // Generate the bad character and good suffix tables
+    // using the pattern.
+    fn build_tables(&mut self) {
+        if self.bad_char_table.is_none() {
+            self.bad_char_table = Some(bad_character_table(self.pattern));
+        }
+        if self.good_suffix_table.is_none() {
+            self.good_suffix_table = Some(good_suffix_table(self.pattern.as_bytes()));
+        }
+    }
+}
+
+// `good_suffix_table[p]` is how far the pattern can be shifted when
+// the text matched the pattern after `p`, but not at `p`.
+// The first entry is also the shift to use after a full match.
+pub fn good_suffix_table(pattern: &[u8]) -> Vec<usize> {
+    let m = pattern.len();
+
+    // `shift[i]` is the shift for when the suffix starting at `i`
+    // matched, and `border[i]` is where the widest border of that
+    // suffix starts.
+    let mut shift = vec![0; m + 1];
+    let mut border = vec![0; m + 1];
+
+    // For each suffix, from the shortest, follow the borders
+    // of the previous suffix until one can be extended to the left.
+    // Any border which can't be extended is a reoccurence of a suffix
+    // preceded by a different character, so if the suffix after that
+    // border has no shift yet, it can shift to line up with it.
+    let (mut i, mut j) = (m, m + 1);
+    border[i] = j;
+    while i > 0 {
+        while j <= m && pattern[i - 1] != pattern[j - 1] {
+            if shift[j] == 0 {
+                shift[j] = j - i;
+            }
+            j = border[j];
+        }
+        i -= 1;
+        j -= 1;
+        border[i] = j;
+    }
+
+    // Any suffix which doesn't reoccur can still shift so that
+    // the widest border of the whole pattern which fits in the
+    // suffix lines up with it.
+    j = border[0];
+    for (i, s) in shift.iter_mut().enumerate() {
+        if *s == 0 {
+            *s = j;
+        }
+        if i == j {
+            j = border[j];
+        }
+    }
+
+    // Index by the mismatched position rather than by the suffix.
+    shift.remove(0);
+    shift
+}
+
+// `suffix_table[i]` is the length of the longest suffix of the pattern
+// which also ends at `i`, so the last entry is the whole length.
+// This is the Z array of the reversed pattern, reversed.
+fn suffix_table(pattern: &[u8]) -> Vec<usize> {
+    let reversed = pattern.iter().rev().cloned().collect::<Vec<_>>();
+    let mut suffix_table = z_array(&reversed);
+    suffix_table.reverse();
+    if let Some(last) = suffix_table.last_mut() {
+        *last = pattern.len();
+    }
+    suffix_table
+}
+
+pub fn bm_search(pattern: &str, text: &str,
+                 bad_char_table: &[usize], good_suffix_table: &[usize]) -> Option<usize> {
+    bm_search_by(pattern.as_bytes(), text.as_bytes(),
+                 bad_char_table, good_suffix_table, |a, b| a == b)
+}
+
+// `bm_search`, but with each pair of bytes compared by `eq`.
+fn bm_search_by<F>(pattern: &[u8], text: &[u8],
+                   bad_char_table: &[usize], good_suffix_table: &[usize],
+                   mut eq: F) -> Option<usize>
+    where F: FnMut(u8, u8) -> bool {
+
+    // Like `str::find`, an empty pattern matches at the start.
+    if pattern.is_empty() {
+        return Some(0);
+    }
+
+    let mut t = 0;
+    // While there's enough room in the text for the pattern:
+    while t + pattern.len() <= text.len() {
+        // Starting at the end of the pattern,
+        // while the pattern matches the text,
+        // move back.
+        let mut p = pattern.len() - 1;
+        while eq(text[t+p], pattern[p]) {
+            // If we reached the start of the pattern, return
+            // the pattern's start position in the text.
+            if p == 0 {
+                return Some(t)
+            }
+            p -= 1;
+        }
+        // There was a mismatch.
+        // Shift by whichever of the bad character rule
+        // and the good suffix rule allows the bigger jump.
+        let bad_char_shift = bad_character_shift(bad_char_table, text[t+p], pattern.len() - 1 - p);
+        t += cmp::max(bad_char_shift, good_suffix_table[p]);
+    }
+    None
+}
+
+// `bm_search_by`, but with the Apostolico-Giancarlo rule.
+fn ag_search_by<F>(pattern: &[u8], text: &[u8],
+                   bad_char_table: &[usize], good_suffix_table: &[usize],
+                   suffix_table: &[usize], mut eq: F) -> Option<usize>
+    where F: FnMut(u8, u8) -> bool {
+
+    // Like `str::find`, an empty pattern matches at the start.
+    if pattern.is_empty() {
+        return Some(0);
+    }
+
+    let m = pattern.len();
+    // `skip[i]` is how much of the pattern matched the text ending
+    // at `i` in the current window, or zero if it hasn't been compared.
+    let mut skip = vec![0; m];
+    let mut t = 0;
+    while t + m <= text.len() {
+        // `p` is how much of the start of the pattern is left to
+        // match, so the mismatch, if there is one, is at `p - 1`.
+        let mut p = m;
+        while p > 0 {
+            let (matched, suffix) = (skip[p - 1], suffix_table[p - 1]);
+            if matched == 0 {
+                // Nothing is known here, so compare.
+                if !eq(text[t + p - 1], pattern[p - 1]) {
+                    break;
+                }
+                p -= 1;
+            } else if matched == suffix {
+                // The text and the pattern both match the same suffix
+                // of the pattern here, so they match each other, and
+                // the comparison carries on before it.
+                p -= matched;
+            } else {
+                // One of them matches more of the suffix than the other,
+                // so they differ just past the shorter match. If the
+                // pattern's suffix reaches its start, that's a match.
+                p -= cmp::min(matched, suffix);
+                break;
+            }
+        }
+        if p == 0 {
+            return Some(t);
+        }
+
+        // There was a mismatch, so remember how much matched, then
+        // shift like `bm_search_by` and move what's known along with it.
+        let mismatch = p - 1;
+        skip[m - 1] = m - 1 - mismatch;
+        let bad_char_shift = bad_character_shift(bad_char_table, text[t + mismatch], m - 1 - mismatch);
+        let shift = cmp::max(bad_char_shift, good_suffix_table[mismatch]);
+        t += shift;
+        skip.copy_within(shift.., 0);
+        for matched in skip[m - shift..].iter_mut() {
+            *matched = 0;
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod correct_return {
+    use std::string::String;
+    use std::vec::Vec;
+    use super::{bm_search_by, ag_search_by, good_suffix_table, suffix_table, BoyerMoorePattern};
+    use {bad_character_table, bad_character_table_bytes, bmh_search_by, linear_search};
+    use correct_return::{CASES, TEXT, Random};
+
+    #[test]
+    fn bm() {
+        for &(want, pattern) in CASES.iter() {
+            let mut searcher = BoyerMoorePattern::new(pattern);
+            assert_eq!(searcher.bm(TEXT), want);
+            assert_eq!(searcher.ag(TEXT), want);
+        }
+    }
+
+    #[test]
+    fn fewer_comparisons_than_bmh() {
+        // The last character of the pattern keeps matching,
+        // but the bad character rule can only shift by one.
+        let pattern = "aaaaab";
+        let text = "b".repeat(1000) + "a".repeat(1000).as_str() + pattern;
+        let bad_char_table = bad_character_table(pattern);
+
+        let mut bmh_comparisons = 0;
+        let bmh = bmh_search_by(pattern.as_bytes(), text.as_bytes(), &bad_char_table, |a, b| {
+            bmh_comparisons += 1;
+            a == b
+        });
+
+        let mut bm_comparisons = 0;
+        let bm = bm_search_by(pattern.as_bytes(), text.as_bytes(),
+                              &bad_char_table, &good_suffix_table(pattern.as_bytes()), |a, b| {
+            bm_comparisons += 1;
+            a == b
+        });
+
+        assert_eq!(bmh, Some(2000));
+        assert_eq!(bm, bmh);
+        assert!(bm_comparisons < bmh_comparisons,
+                "BM made {} comparisons, BMH made {}", bm_comparisons, bmh_comparisons);
+    }
+    #[test]
+    fn good_suffix_table_fixtures() {
+        assert_eq!(good_suffix_table(b"GCAGAGAG"), vec![7, 7, 7, 2, 7, 4, 7, 1]);
+        // After matching "n", it reoccurs twice, but after the same "a"
+        // which just mismatched, so the pattern shifts right past it.
+        assert_eq!(good_suffix_table(b"anpanman"), vec![6, 6, 6, 6, 6, 3, 8, 1]);
+        assert_eq!(good_suffix_table(b""), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn suffix_table_fixture() {
+        assert_eq!(suffix_table(b"abaab"), vec![0, 2, 0, 0, 5]);
+        assert_eq!(suffix_table(b"aaa"), vec![1, 2, 3]);
+        assert_eq!(suffix_table(b""), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn ag_agrees_with_linear() {
+        let mut random = Random(0x14057b7ef767814f);
+        for _ in 0..10000 {
+            let pattern = String::from_utf8(random.bytes(6, 2)).unwrap();
+            let text = String::from_utf8(random.bytes(30, 2)).unwrap();
+            assert_eq!(BoyerMoorePattern::new(&pattern).ag(&text),
+                       linear_search(pattern.as_bytes(), text.as_bytes()),
+                       "{:?} in {:?}", pattern, text);
+        }
+    }
+
+    #[test]
+    fn ag_periodic_text() {
+        // The pattern keeps matching its last run of "a"s, then the "b"
+        // before it, before mismatching, and the good suffix rule can
+        // only shift by enough to line up the next run. BM compares the
+        // same runs again after each shift, while AG remembers them.
+        let pattern = "baaaaaaaabaaaaaaaa";
+        let text = "baaaaaaaaa".repeat(100) + pattern;
+        let (p, t) = (pattern.as_bytes(), text.as_bytes());
+        let (bad_char_table, good_suffix_table) = (bad_character_table_bytes(p), good_suffix_table(p));
+
+        let mut bm_comparisons = 0;
+        let bm = bm_search_by(p, t, &bad_char_table, &good_suffix_table, |a, b| {
+            bm_comparisons += 1;
+            a == b
+        });
+
+        let mut ag_comparisons = 0;
+        let ag = ag_search_by(p, t, &bad_char_table, &good_suffix_table, &suffix_table(p), |a, b| {
+            ag_comparisons += 1;
+            a == b
+        });
+
+        assert_eq!(bm, Some(1000));
+        assert_eq!(ag, bm);
+        assert_eq!(BoyerMoorePattern::new(pattern).ag(&text), bm);
+        // AG makes at most one and a half comparisons per byte of text.
+        assert!(ag_comparisons <= 3 * t.len() / 2, "AG made {} comparisons", ag_comparisons);
+        assert!(bm_comparisons > 2 * ag_comparisons,
+                "BM made {} comparisons, AG made {}", bm_comparisons, ag_comparisons);
+    }
+}
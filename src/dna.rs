@@ -0,0 +1,191 @@
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use super::{border_table, SearchError};
+use super::bitap::MAX_PATTERN_LEN;
+
+// Searches DNA with Shift-Or, which only needs a mask for each of the
+// four nucleotides rather than one for every byte.
+// Patterns have to be made of the uppercase nucleotides "A", "C", "G"
+// and "T", and anything else is rejected; there's no wildcard for "N".
+// Texts can have anything in them, but nothing else ever matches.
+#[derive(Clone, Debug)]
+pub struct DnaSearcher {
+    // The pattern as 2 bit codes.
+    pattern: Vec<u8>,
+    // `masks[code]` has bit `i` clear when `pattern[i]` is `code`.
+    masks: [u64; 4],
+    // Patterns which don't fit in the state are searched for with KMP.
+    borders: Option<Vec<usize>>,
+}
+
+impl DnaSearcher {
+    pub fn new(pattern: &[u8]) -> Result<DnaSearcher, SearchError> {
+        let pattern = pattern.iter().enumerate().map(|(position, &byte)| {
+            nucleotide_code(byte).ok_or(SearchError::InvalidNucleotide{ position, byte })
+        }).collect::<Result<Vec<_>, _>>()?;
+
+        let mut masks = [!0u64; 4];
+        let mut borders = None;
+        if pattern.len() > MAX_PATTERN_LEN {
+            borders = Some(border_table(&pattern));
+        } else {
+            for (i, &code) in pattern.iter().enumerate() {
+                masks[code as usize] &= !(1 << i);
+            }
+        }
+        Ok(DnaSearcher{ pattern, masks, borders })
+    }
+
+    pub fn find(&self, text: &[u8]) -> Option<usize> {
+        self.find_codes(text.iter().map(|&b| nucleotide_code(b)))
+    }
+
+    // Searches the first `len` nucleotides of a text packed by `pack_dna`.
+    pub fn find_packed(&self, packed: &[u8], len: usize) -> Option<usize> {
+        assert!(len <= packed.len() * 4, "the packed text is shorter than its length");
+        self.find_codes((0..len).map(|i| Some(packed[i / 4] >> (i % 4 * 2) & 0b11)))
+    }
+
+    // `None` is anything which isn't a nucleotide.
+    fn find_codes<I>(&self, text: I) -> Option<usize>
+        where I: Iterator<Item = Option<u8>> {
+        let m = self.pattern.len();
+        // Like `str::find`, an empty pattern matches at the start.
+        if m == 0 {
+            return Some(0);
+        }
+
+        if let Some(ref borders) = self.borders {
+            // KMP, a code at a time, like a `SegmentedSearcher`.
+            let mut p = 0;
+            for (t, code) in text.enumerate() {
+                let code = match code {
+                    Some(code) => code,
+                    None => {
+                        p = 0;
+                        continue;
+                    },
+                };
+                while p > 0 && self.pattern[p] != code {
+                    p = borders[p - 1];
+                }
+                if self.pattern[p] == code {
+                    p += 1;
+                }
+                if p == m {
+                    return Some(t + 1 - m);
+                }
+            }
+            return None;
+        }
+
+        // The same as `shift_or`.
+        let last = 1 << (m - 1);
+        let mut state = !0u64;
+        for (t, code) in text.enumerate() {
+            state = match code {
+                Some(code) => (state << 1) | self.masks[code as usize],
+                None => !0,
+            };
+            if state & last == 0 {
+                return Some(t + 1 - m);
+            }
+        }
+        None
+    }
+}
+
+// Packs a sequence of nucleotides into 2 bits each, four to a byte,
+// with the first in the lowest bits. The length has to be kept
+// alongside it, since the last byte might not be full.
+pub fn pack_dna(sequence: &[u8]) -> Result<Vec<u8>, SearchError> {
+    let mut packed = vec![0; sequence.len().div_ceil(4)];
+    for (position, &byte) in sequence.iter().enumerate() {
+        let code = nucleotide_code(byte).ok_or(SearchError::InvalidNucleotide{ position, byte })?;
+        packed[position / 4] |= code << (position % 4 * 2);
+    }
+    Ok(packed)
+}
+
+fn nucleotide_code(b: u8) -> Option<u8> {
+    match b {
+        b'A' => Some(0),
+        b'C' => Some(1),
+        b'G' => Some(2),
+        b'T' => Some(3),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod correct_return {
+    use std::vec::Vec;
+    use super::{pack_dna, DnaSearcher};
+    use {linear_search, SearchError};
+    use bitap::MAX_PATTERN_LEN;
+    use correct_return::Random;
+
+    // Random nucleotides, from `Random`'s letters.
+    fn nucleotides(random: &mut Random, max_len: u64) -> Vec<u8> {
+        random.bytes(max_len, 4).into_iter().map(|b| b"ACGT"[(b - b'a') as usize]).collect()
+    }
+
+    #[test]
+    fn find() {
+        let searcher = DnaSearcher::new(b"ACG").unwrap();
+        assert_eq!(searcher.find(b"TTACGGT"), Some(2));
+        assert_eq!(searcher.find(b"TTACNGT"), None);
+        assert_eq!(searcher.find(b"acgACG"), Some(3));
+        assert_eq!(DnaSearcher::new(b"").unwrap().find(b""), Some(0));
+    }
+
+    #[test]
+    fn invalid_nucleotides() {
+        assert_eq!(DnaSearcher::new(b"ACNG").unwrap_err(),
+                   SearchError::InvalidNucleotide{ position: 2, byte: b'N' });
+        assert!(DnaSearcher::new(b"acg").is_err());
+        assert_eq!(pack_dna(b"AX").unwrap_err(),
+                   SearchError::InvalidNucleotide{ position: 1, byte: b'X' });
+    }
+
+    #[test]
+    fn packed() {
+        assert_eq!(pack_dna(b"ACGTC").unwrap(), vec![0b11100100, 0b01]);
+        let searcher = DnaSearcher::new(b"ACG").unwrap();
+        let packed = pack_dna(b"TTACGGT").unwrap();
+        assert_eq!(searcher.find_packed(&packed, 7), Some(2));
+        assert_eq!(searcher.find_packed(&packed, 4), None);
+        // The padding in the last byte reads as "A"s, but isn't searched.
+        let searcher = DnaSearcher::new(b"TA").unwrap();
+        assert_eq!(searcher.find_packed(&pack_dna(b"CCT").unwrap(), 3), None);
+    }
+
+    #[test]
+    fn agrees_with_linear() {
+        let mut random = Random(0x6c8e9cf570932bd5);
+        for _ in 0..10000 {
+            let pattern = nucleotides(&mut random, 5);
+            let text = nucleotides(&mut random, 30);
+            let searcher = DnaSearcher::new(&pattern).unwrap();
+            let want = linear_search(&pattern, &text);
+            assert_eq!(searcher.find(&text), want, "{:?} in {:?}", pattern, text);
+            assert_eq!(searcher.find_packed(&pack_dna(&text).unwrap(), text.len()), want);
+        }
+    }
+
+    #[test]
+    fn long_patterns() {
+        let text = b"ACGTTGCA".repeat(30);
+        for len in MAX_PATTERN_LEN - 1..MAX_PATTERN_LEN + 2 {
+            let pattern = &text[3..3 + len];
+            let searcher = DnaSearcher::new(pattern).unwrap();
+            assert_eq!(searcher.find(&text), Some(3));
+            assert_eq!(searcher.find(&text[4..]), Some(7));
+            assert_eq!(searcher.find_packed(&pack_dna(&text).unwrap(), text.len()), Some(3));
+            let mut broken = text.clone();
+            broken[10] = b'N';
+            assert_eq!(searcher.find(&broken[..len + 10]), None);
+        }
+    }
+}
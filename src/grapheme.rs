@@ -0,0 +1,95 @@
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::KMPPattern;
+
+// Searches for the pattern as a run of whole grapheme clusters, which
+// are what a reader sees as single characters, e.g. an "e" with an
+// accent on it, or an emoji with a skin tone. A char search can match
+// part of one, like the "e" of "e\u{301}", where this won't. Returns
+// the index of the cluster the match starts at, not a byte index.
+// The clusters are the extended ones from Unicode's UAX #29.
+pub fn grapheme_search(pattern: &str, text: &str) -> Option<usize> {
+    let pattern = graphemes(pattern);
+    let text = graphemes(text);
+    KMPPattern::new(&pattern[..]).kmp(&text[..])
+}
+
+fn graphemes(s: &str) -> Vec<&str> {
+    s.graphemes(true).collect()
+}
+
+#[cfg(test)]
+mod correct_return {
+    use std::vec::Vec;
+    use super::{graphemes, grapheme_search};
+    use correct_return::{CASES, TEXT};
+
+    #[test]
+    fn clusters() {
+        assert_eq!(graphemes("cafe\u{301}!"), vec!["c", "a", "f", "e\u{301}", "!"]);
+        assert_eq!(graphemes("a\r\nb\n\r"), vec!["a", "\r\n", "b", "\n", "\r"]);
+        // A thumbs up with a skin tone, and a family joined by ZWJs.
+        assert_eq!(graphemes("\u{1F44D}\u{1F3FD}\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}x"),
+                   vec!["\u{1F44D}\u{1F3FD}", "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}", "x"]);
+        // Three regional indicators are a flag and the start of another.
+        assert_eq!(graphemes("\u{1F1EC}\u{1F1E7}\u{1F1EB}"), vec!["\u{1F1EC}\u{1F1E7}", "\u{1F1EB}"]);
+        assert_eq!(graphemes(""), Vec::<&str>::new());
+
+        // Marks from other scripts: Hebrew points, an Arabic vowel,
+        // a Devanagari vowel sign which takes up its own space,
+        // and a Hangul syllable spelled out in jamo.
+        assert_eq!(graphemes("\u{5E9}\u{5BC}\u{5B8}\u{5DC}"), vec!["\u{5E9}\u{5BC}\u{5B8}", "\u{5DC}"]);
+        assert_eq!(graphemes("\u{628}\u{64E}\u{627}"), vec!["\u{628}\u{64E}", "\u{627}"]);
+        assert_eq!(graphemes("\u{915}\u{93F}\u{924}"), vec!["\u{915}\u{93F}", "\u{924}"]);
+        assert_eq!(graphemes("\u{1100}\u{1161}\u{11A8}a"), vec!["\u{1100}\u{1161}\u{11A8}", "a"]);
+    }
+
+    #[test]
+    fn ascii_agrees_with_find() {
+        // Every ASCII char is its own cluster,
+        // so cluster indices are byte indices.
+        for &(want, pattern) in CASES.iter() {
+            assert_eq!(grapheme_search(pattern, TEXT), want, "{:?}", pattern);
+        }
+    }
+
+    #[test]
+    fn no_partial_clusters() {
+        let text = "re\u{301}sume\u{301} resume";
+        // A char search finds the "e" of the "e" with an accent.
+        assert_eq!(text.find("e"), Some(1));
+        assert_eq!(grapheme_search("e", text), Some(8));
+        assert_eq!(grapheme_search("resume", text), Some(7));
+        assert_eq!(grapheme_search("e\u{301}", text), Some(1));
+
+        // The thumbs up without its skin tone is a different character.
+        let text = "\u{1F44D}\u{1F3FD} \u{1F44D}";
+        assert_eq!(text.find('\u{1F44D}'), Some(0));
+        assert_eq!(grapheme_search("\u{1F44D}", text), Some(2));
+        assert_eq!(grapheme_search("\u{1F3FD}", text), None);
+
+        // A flag isn't found in the middle of a pair of flags.
+        let text = "\u{1F1EC}\u{1F1E7}\u{1F1EB}\u{1F1F7}";
+        assert_eq!(text.find("\u{1F1E7}\u{1F1EB}"), Some(4));
+        assert_eq!(grapheme_search("\u{1F1E7}\u{1F1EB}", text), None);
+        assert_eq!(grapheme_search("\u{1F1EB}\u{1F1F7}", text), Some(1));
+
+        // The bare letters of other scripts aren't found where
+        // they have marks on them.
+        let text = "\u{5E9}\u{5B8}\u{5DC} \u{5E9}";
+        assert_eq!(text.find('\u{5E9}'), Some(0));
+        assert_eq!(grapheme_search("\u{5E9}", text), Some(3));
+        let text = "\u{915}\u{93F} \u{915}";
+        assert_eq!(grapheme_search("\u{915}", text), Some(2));
+        assert_eq!(grapheme_search("\u{915}\u{93F}", text), Some(0));
+        let text = "\u{1100}\u{1161}\u{11A8}\u{1100}\u{1161}";
+        assert_eq!(text.find("\u{1100}\u{1161}"), Some(0));
+        assert_eq!(grapheme_search("\u{1100}\u{1161}", text), Some(1));
+
+        assert_eq!(grapheme_search("", text), Some(0));
+        assert_eq!(grapheme_search("a", ""), None);
+    }
+}
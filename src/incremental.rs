@@ -0,0 +1,100 @@
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use super::kmp_search;
+
+// A KMP pattern which is built up an element at a time, e.g. as it's
+// typed. `border_table` only ever looks back at the borders it has
+// already found, so each new element's border can be found the same
+// way without going over the rest of the pattern again.
+#[derive(Clone, Debug, Default)]
+pub struct IncrementalKmp<C> {
+    pattern: Vec<C>,
+    borders: Vec<usize>,
+}
+
+impl<C> IncrementalKmp<C>
+    where C: PartialEq {
+
+    pub fn new() -> IncrementalKmp<C> {
+        IncrementalKmp{
+            pattern: Vec::new(),
+            borders: Vec::new(),
+        }
+    }
+
+    // Each push can fall back through several borders, but each border
+    // is at most one longer than the last, so over all of the pushes
+    // this is constant time per element.
+    pub fn push(&mut self, c: C) {
+        let border = match self.borders.last() {
+            // A single element has no proper border.
+            None => 0,
+            Some(&longest) => {
+                // Fall back through the borders of the pattern so far
+                // until one can be extended by `c`.
+                let mut b = longest;
+                while self.pattern[b] != c && b != 0 {
+                    b = self.borders[b - 1];
+                }
+                if self.pattern[b] == c { b + 1 } else { 0 }
+            },
+        };
+        self.pattern.push(c);
+        self.borders.push(border);
+    }
+
+    pub fn pattern(&self) -> &[C] {
+        &self.pattern
+    }
+
+    pub fn borders(&self) -> &[usize] {
+        &self.borders
+    }
+
+    pub fn find(&self, text: &[C]) -> Option<usize> {
+        kmp_search(&self.pattern, text, &self.borders)
+    }
+}
+
+#[cfg(test)]
+mod correct_return {
+    use std::vec::Vec;
+    use super::IncrementalKmp;
+    use border_table;
+    use correct_return::{CASES, TEXT, Random};
+
+    #[test]
+    fn find() {
+        for &(want, pattern) in CASES.iter() {
+            let mut searcher = IncrementalKmp::new();
+            for b in pattern.bytes() {
+                searcher.push(b);
+            }
+            assert_eq!(searcher.pattern(), pattern.as_bytes());
+            assert_eq!(searcher.find(TEXT.as_bytes()), want);
+        }
+    }
+
+    #[test]
+    fn borders_at_each_step() {
+        let mut searcher = IncrementalKmp::new();
+        for (i, c) in "abacabab".chars().enumerate() {
+            searcher.push(c);
+            let prefix = "abacabab".chars().take(i + 1).collect::<Vec<_>>();
+            assert_eq!(searcher.borders(), &border_table(&prefix[..])[..]);
+        }
+        assert_eq!(searcher.borders(), &[0, 0, 1, 0, 1, 2, 3, 2]);
+
+        let mut random = Random(0x2127599bf4325c37);
+        for _ in 0..1000 {
+            let pattern = random.bytes(20, 2);
+            let mut searcher = IncrementalKmp::new();
+            for (i, &b) in pattern.iter().enumerate() {
+                searcher.push(b);
+                assert_eq!(searcher.borders(), &border_table(&pattern[..i + 1])[..],
+                           "{:?}", &pattern[..i + 1]);
+            }
+        }
+    }
+}
@@ -1,16 +1,181 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+// Without `std`, the tables are allocated with `alloc` instead.
+#[cfg(not(feature = "std"))]
+#[macro_use]
+extern crate alloc;
+// `no_std` brings in `core` itself, so this is only needed with `std`.
+#[cfg(feature = "std")]
+extern crate core;
+// The test harness needs `std` even when the crate doesn't use it,
+// so the tests link it themselves, without it leaking into the crate.
+#[cfg(all(test, not(feature = "std")))]
+#[macro_use(thread_local)]
+extern crate std;
+
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "unicode")]
+extern crate unicode_segmentation;
+#[cfg(all(test, feature = "serde"))]
+extern crate serde_json;
+#[cfg(test)]
+extern crate proptest;
+
+#[cfg(not(feature = "std"))]
+use alloc::string::{String, ToString};
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use core::{cmp, fmt, iter};
+use core::ops::{ControlFlow, Range};
+
+mod aho_corasick;
+mod automaton;
+mod bitap;
+#[cfg(feature = "std")]
+mod bmh_generic;
+mod boyer_moore;
+mod dna;
+#[cfg(feature = "unicode")]
+mod grapheme;
+mod incremental;
+mod memchr;
+mod options;
+#[cfg(feature = "parallel")]
+mod parallel;
+mod pattern_set;
+mod preprocessed;
+mod rabin_karp;
+mod segmented;
+#[cfg(feature = "std")]
+mod stream;
+mod sunday;
+mod two_way;
+mod z_algorithm;
+
+pub use aho_corasick::{AhoCorasick, MatchKind};
+pub use automaton::{KmpAutomaton, StreamResult};
+pub use bitap::{shift_or, shift_and, ClassPattern, bitap_fuzzy, fuzzy_edit_search, bndm, wildcard_search};
+#[cfg(feature = "std")]
+pub use bmh_generic::BMHGeneric;
+pub use boyer_moore::{bm_search, good_suffix_table, BoyerMoorePattern};
+pub use dna::{pack_dna, DnaSearcher};
+#[cfg(feature = "unicode")]
+pub use grapheme::grapheme_search;
+pub use incremental::IncrementalKmp;
+pub use memchr::{find_first_of, find_last_of};
+pub use options::{SearchOptions, SearchMatches};
+pub use pattern_set::{contains_any, PatternSet};
+pub use preprocessed::{PreprocessedKmp, PreprocessedBmh};
+pub use rabin_karp::{rabin_karp, RabinKarpPattern};
+pub use segmented::SegmentedSearcher;
+#[cfg(feature = "std")]
+pub use stream::StreamSearcher;
+pub use sunday::SundayPattern;
+pub use two_way::two_way;
+pub use z_algorithm::{z_array, z_search};
+
+// Where a match was found, in the same units as the text that was
+// searched, e.g. chars for a `KMPPattern<char>`, bytes for a `BMHPattern`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Match {
+    pub start: usize,
+    // Exclusive.
+    pub end: usize,
+}
+
+impl Match {
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+}
+
+// Why a search couldn't be run, for the `try_` searches.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SearchError {
+    // An empty pattern trivially matches everywhere, which is more
+    // often a bug in the caller than what they wanted.
+    EmptyPattern,
+    // A `DnaSearcher` pattern had something other than "A", "C", "G"
+    // or "T" at `position`.
+    InvalidNucleotide{ position: usize, byte: u8 },
+}
+
+impl fmt::Display for SearchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SearchError::EmptyPattern => f.write_str("the pattern is empty"),
+            SearchError::InvalidNucleotide{ position, byte } =>
+                write!(f, "{:?} at {} isn't a nucleotide", byte as char, position),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SearchError {}
+
+// A preprocessed pattern which can be searched for in a text,
+// whichever algorithm it uses.
+pub trait Searcher<C> {
+    fn find(&mut self, text: &[C]) -> Option<usize>;
+}
+
+#[derive(Clone, Debug)]
+pub struct LinearPattern<'s, C: 's> {
+    pattern: &'s[C],
+}
+
+impl<'s, C> LinearPattern<'s, C>
+    where C: PartialEq {
+    
+    pub fn new(pattern: &'s[C]) -> LinearPattern<'s, C> {
+        LinearPattern{
+            pattern,
+        }
+    }
+    
+    pub fn linear(&self, text: &[C]) -> Option<usize> {
+        linear_search(self.pattern, text)
+    }
+}
+
+impl<'s, C> Searcher<C> for LinearPattern<'s, C>
+    where C: PartialEq {
+    
+    fn find(&mut self, text: &[C]) -> Option<usize> {
+        self.linear(text)
+    }
+}
+
+#[derive(Clone, Debug)]
 pub struct KMPPattern<'s, C: 's> {
     pattern: &'s[C],
     borders: Option<Vec<usize>>,
+    reverse_borders: Option<Vec<usize>>,
 }
 
 impl<'s, C> KMPPattern<'s, C>
     where C: PartialEq {
     
     pub fn new(pattern: &'s[C]) -> KMPPattern<'s, C> {
-        return KMPPattern{
-            pattern: pattern,
+        KMPPattern{
+            pattern,
             borders: None,
-        };
+            reverse_borders: None,
+        }
+    }
+    
+    // Uses a border table which was already generated,
+    // e.g. by `border_table`, instead of generating it again.
+    pub fn with_borders(pattern: &'s[C], borders: Vec<usize>) -> KMPPattern<'s, C> {
+        debug_assert_eq!(borders.len(), pattern.len(),
+                         "there must be one border per pattern element");
+        KMPPattern{
+            pattern,
+            borders: Some(borders),
+            reverse_borders: None,
+        }
     }
     
     pub fn linear(&self, text: &[C]) -> Option<usize> {
@@ -20,248 +185,2561 @@ impl<'s, C> KMPPattern<'s, C>
     pub fn kmp(&mut self, text: &[C]) -> Option<usize>
         where C: PartialEq {
         
-        // Generate the prefix table using the pattern.
-        let borders: &[usize] = match self.borders {
-            None => {
-                self.borders = Some(border_table(self.pattern));
-                &self.borders.as_ref().unwrap()[..]
-            },
-            Some(ref b) => &b[..]
-        };
+        // A pattern longer than the text can't fit anywhere in it,
+        // so there's no need to build the prefix table.
+        let pattern = self.pattern;
+        if pattern.len() > text.len() {
+            return None;
+        }
+        // A single element has no borders to fall back to, so it's
+        // just looked for directly, also without building the table.
+        if pattern.len() == 1 {
+            return text.iter().position(|c| *c == pattern[0]);
+        }
+        let borders = self.cached_borders();
       
         // Search the text using the pattern and prefix table.
-        kmp_search(self.pattern, text, borders)
+        kmp_search(pattern, text, borders)
     }
-}
-
-pub struct BMHPattern<'s> {
-    u8_kmp: KMPPattern<'s, u8>,
     
-    pattern: &'s str,
-    bad_char_table: Option<Vec<usize>>,
-}
-
-impl<'s> BMHPattern<'s> {
-    pub fn new<'p: 's>(pattern: &'p str) -> BMHPattern<'s> {
-        BMHPattern{
-            u8_kmp: KMPPattern{
-                pattern: pattern.as_bytes(),
-                borders: None,
-            },
-            
-            pattern: pattern,
-            bad_char_table: None,
+    pub fn contains(&mut self, text: &[C]) -> bool {
+        self.kmp(text).is_some()
+    }
+    
+    pub fn find_match(&mut self, text: &[C]) -> Option<Match> {
+        let len = self.pattern.len();
+        self.kmp(text).map(|start| Match{ start, end: start + len })
+    }
+    
+    // The part of the text which matched, borrowed from the text.
+    pub fn find_slice<'t>(&mut self, text: &'t [C]) -> Option<&'t [C]> {
+        self.find_match(text).map(|m| &text[m.range()])
+    }
+    
+    // The start of the first match, and the rest of the text after it,
+    // e.g. to carry on searching from there.
+    pub fn find_split<'t>(&mut self, text: &'t [C]) -> Option<(usize, &'t [C])> {
+        self.find_match(text).map(|m| (m.start, &text[m.end..]))
+    }
+    
+    pub fn find_from(&mut self, text: &[C], start: usize) -> Option<usize> {
+        if start > text.len() {
+            return None;
         }
+        // Search the rest of the text, keeping offsets absolute.
+        self.kmp(&text[start..]).map(|i| start + i)
     }
     
-    pub fn linear(&self, text: &str) -> Option<usize> {
-        self.u8_kmp.linear(text.as_bytes())
+    // For resuming a search when the first `already_scanned` elements
+    // are known not to start a match. Only the rest of the text is
+    // searched, as its own slice, so even KMP's fallbacks never look
+    // back into the skipped prefix. The index is still into all of it.
+    pub fn find_skip_prefix(&mut self, text: &[C], already_scanned: usize) -> Option<usize> {
+        self.find_from(text, already_scanned)
     }
     
-    pub fn kmp(&mut self, text: &str) -> Option<usize> {
-        self.u8_kmp.kmp(text.as_bytes())
+    // Compares the pattern with the start of the text directly,
+    // without needing the border table.
+    pub fn matches_at_start(&self, text: &[C]) -> bool {
+        text.starts_with(self.pattern)
     }
     
-    pub fn bmh(&mut self, text: &str) -> Option<usize> {
-        // Generate the bad character table using the pattern.
-        let bad_char_table: &[usize] = match self.bad_char_table {
+    pub fn find_in_range(&mut self, text: &[C], range: Range<usize>) -> Option<usize> {
+        // A range running past the end is cut short, and one which is
+        // empty, even for an empty pattern, has nothing to search.
+        let end = cmp::min(range.end, text.len());
+        if range.start >= end {
+            return None;
+        }
+        // Search within the range, keeping offsets absolute.
+        self.kmp(&text[range.start..end]).map(|i| range.start + i)
+    }
+    
+    pub fn find_all<'a>(&'a mut self, text: &'a [C]) -> impl Iterator<Item = usize> + 'a {
+        let pattern = self.pattern;
+        let borders = self.cached_borders();
+        
+        KMPMatches{
+            pattern,
+            borders,
+            text,
+            position: 0,
+            matched: 0,
+            overlapping: false,
+        }
+    }
+    
+    pub fn find_overlapping<'a>(&'a mut self, text: &'a [C]) -> impl Iterator<Item = usize> + 'a {
+        let pattern = self.pattern;
+        let borders = self.cached_borders();
+        
+        KMPMatches{
+            pattern,
+            borders,
+            text,
+            position: 0,
+            matched: 0,
+            overlapping: true,
+        }
+    }
+    
+    pub fn count(&mut self, text: &[C]) -> usize {
+        self.find_all(text).count()
+    }
+    
+    // The length of the longest prefix of the pattern which the text ends
+    // with, i.e. how much of a match is in progress when the text runs out,
+    // which is all that needs carrying over to the next chunk of a stream.
+    pub fn longest_partial_suffix_match(&mut self, text: &[C]) -> usize {
+        let pattern = self.pattern;
+        let borders = self.cached_borders();
+        if pattern.is_empty() {
+            return 0;
+        }
+        
+        let mut p = 0;
+        for c in text {
+            // A whole match can only be extended as far as its border.
+            if p == pattern.len() {
+                p = borders[p - 1];
+            }
+            // As in `KMPMatches`.
+            while p != 0 && pattern[p] != *c {
+                p = borders[p - 1];
+            }
+            if pattern[p] == *c {
+                p += 1;
+            }
+        }
+        p
+    }
+    
+    // Calls `f` with the start of each match that `find_all` would
+    // return, without collecting them anywhere.
+    pub fn for_each_match<F>(&mut self, text: &[C], f: F)
+        where F: FnMut(usize) {
+        self.find_all(text).for_each(f)
+    }
+    
+    // `for_each_match`, but `f` can stop the search early by breaking.
+    pub fn try_for_each_match<B, F>(&mut self, text: &[C], f: F) -> ControlFlow<B>
+        where F: FnMut(usize) -> ControlFlow<B> {
+        self.find_all(text).try_for_each(f)
+    }
+    
+    pub fn rfind(&mut self, text: &[C]) -> Option<usize> {
+        let pattern = self.pattern;
+        // Like `str::rfind`, an empty pattern matches at the very end.
+        if pattern.is_empty() {
+            return Some(text.len());
+        }
+        
+        // Generate the prefix table using the reversed pattern.
+        let reverse_borders: &[usize] = match self.reverse_borders {
             None => {
-                self.bad_char_table = Some(bad_character_table(self.pattern));
-                &self.bad_char_table.as_ref().unwrap()[..]
+                self.reverse_borders = Some(reverse_border_table(pattern));
+                &self.reverse_borders.as_ref().unwrap()[..]
             },
             Some(ref b) => &b[..]
         };
-      
-        // Search the text using the pattern and bad character table.
-        bmh_search(self.pattern, text, bad_char_table)
+        
+        // Search the reversed text using the reversed pattern.
+        kmp_rsearch(pattern, text, reverse_borders)
+    }
+    
+    pub fn borders(&mut self) -> &[usize] {
+        self.cached_borders()
+    }
+    
+    // Builds the border table now rather than on the first search,
+    // e.g. so a benchmark can time the two separately.
+    pub fn preprocess(&mut self) {
+        self.cached_borders();
+    }
+    
+    pub fn clear_cache(&mut self) {
+        self.borders = None;
+        self.reverse_borders = None;
+    }
+    
+    fn cached_borders(&mut self) -> &[usize] {
+        // An empty pattern has no borders to look up.
+        if self.pattern.is_empty() {
+            return &[];
+        }
+        // Generate the prefix table using the pattern.
+        match self.borders {
+            None => {
+                self.borders = Some(border_table(self.pattern));
+                &self.borders.as_ref().unwrap()[..]
+            },
+            Some(ref b) => &b[..]
+        }
     }
 }
 
-pub fn linear_search<C>(pattern: &[C], text: &[C]) -> Option<usize>
+impl<'s, C> From<&'s [C]> for KMPPattern<'s, C>
     where C: PartialEq {
     
-    // For each starting point in the text:
-    'text:
-    for i_text in 0..text.len() {
-        // For each character in the pattern:
-        for i_pattern in 0..pattern.len() {
-            // If there is a mismatch, try the next position.
-            if text[i_text + i_pattern] != pattern[i_pattern] {
-                continue 'text;
-            }
-            // If we reached the end of the text, the pattern isn't
-            // in the text, so don't try any more positions.
-            if i_text + i_pattern == text.len() {
-                break 'text;
-            }
-        }
-        // If we went through the whole pattern with no mismatch,
-        // we found the first instance of the pattern.
-        return Some(i_text);
+    fn from(pattern: &'s [C]) -> KMPPattern<'s, C> {
+        KMPPattern::new(pattern)
     }
-    return None
 }
 
-pub fn border_table<C>(pattern: &[C]) -> Vec<usize>
+impl<'s, C> Searcher<C> for KMPPattern<'s, C>
     where C: PartialEq {
-        
-    let mut borders = Vec::with_capacity(pattern.len());
-  
-    // TODO: why do we always start with these?
-    borders.push(0);
-    borders.push(0);
     
-    // For each prefix `p` of length `i` in the pattern,
-    // followed by the character `c`,
-    // starting with the prefix of length 2:
-    for (i, c) in pattern.iter().skip(1).enumerate().skip(1) {
-        // Starting with longest border of p,
-        // keep checking the current border's longest border until
-        // (`the prefix of length b`+`c`) is a border of (`p`+`c`),
-        // or there are no more borders to check.
-        let mut b = borders[i];
-        while pattern[b] != *c && b != 0 {
-            b = borders[b]
-        }
-        // If an extensible border was found, extend it,
-        // otherwise this prefix has no border.
-        if pattern[b] == *c {
-            borders.push(b + 1);
-        } else {
-            borders.push(0);
-        }
+    fn find(&mut self, text: &[C]) -> Option<usize> {
+        self.kmp(text)
     }
-    assert_eq!(pattern.len(), borders.len());
-    
-    borders
 }
 
-pub fn kmp_search<C>(pattern: &[C], text: &[C], borders: &[usize]) -> Option<usize>
+impl<'s> KMPPattern<'s, char> {
+    pub fn find_word(&mut self, text: &[char]) -> Option<usize> {
+        let len = self.pattern.len();
+        // Matches can overlap, so the second of two overlapping
+        // matches can still be a word when the first isn't.
+        self.find_overlapping(text).find(|&start| {
+            let before = start.checked_sub(1).map(|i| text[i]);
+            let after = text.get(start + len).cloned();
+            is_word_boundary(before, after)
+        })
+    }
+}
+
+struct KMPMatches<'a, C: 'a> {
+    pattern: &'a [C],
+    borders: &'a [usize],
+    text: &'a [C],
+    // The next character of the text to check.
+    position: usize,
+    // How much of the pattern the text before `position` matches.
+    matched: usize,
+    overlapping: bool,
+}
+
+impl<'a, C> Iterator for KMPMatches<'a, C>
     where C: PartialEq {
-    let mut t = 0;
-    let mut p = 0;
-    // While we haven't reached the last possible starting point
-    // for the pattern in the text.
-    while t+p < text.len() {
-        // If there is a match, move forward in the pattern.
-        if text[t+p] == pattern[p] {
-            p += 1;
-            // If we reached the end of the pattern, return 
-            // the substring's starting position in the text.
+    
+    type Item = usize;
+    
+    fn next(&mut self) -> Option<usize> {
+        // An empty pattern would match forever without advancing,
+        // so it gets no matches at all.
+        if self.pattern.is_empty() {
+            return None;
+        }
+        let pattern = self.pattern;
+        let borders = self.borders;
+        
+        while self.position < self.text.len() {
+            let c = &self.text[self.position];
+            self.position += 1;
+            
+            // Fall back through the borders of the matched prefix
+            // until one can be extended by `c`, or there are none left.
+            let mut p = self.matched;
+            while p != 0 && pattern[p] != *c {
+                p = borders[p - 1];
+            }
+            if pattern[p] == *c {
+                p += 1;
+            }
+            
             if p == pattern.len() {
-                return Some(t)    
+                // An overlapping match can reuse the longest border of
+                // the whole pattern, otherwise start again from scratch.
+                // Either way the text is only ever read forwards, so like
+                // the Galil rule for Boyer-Moore, nothing already known to
+                // match is compared again, even for a periodic pattern.
+                self.matched = if self.overlapping { borders[p - 1] } else { 0 };
+                return Some(self.position - p);
             }
-            continue;
-        }
-       
-        // There was a mismatch.
-        // If we're at the beginning of the pattern, shift by one.
-        // Otherwise, shift so that the previous characters form
-        // the longest possible prefix of the pattern, then recheck
-        // this character.
-        if p == 0 {
-            t += 1;
-        } else {
-            t += p - borders[p];
-            p = borders[p];
+            self.matched = p;
         }
+        None
     }
-    return None
 }
 
-pub fn bad_character_table(pattern: &str) -> Vec<usize> {
-    // If the character doesn't appear in the pattern,
-    // then we can skip ahead by the length of the whole
-    // pattern if the character appears in the text.
-    let mut bad_char_table =
-        (0..std::u8::MAX).map(|_| -> usize { pattern.len() })
-                         .collect::<Vec<_>>();
-   
-    // Otherwise we should skip ahead by the distance between the
-    // end of the pattern and the last occurence of that character.
-    for (i, c) in pattern.bytes().enumerate() {
-        bad_char_table[c as usize] = pattern.len() - 1 - i;
-    }
-    
-    bad_char_table
+// The longest pattern which `BMHBytesPattern::bmh` looks for the rarest
+// byte of before searching.
+const PREFILTER_MAX_LEN: usize = 8;
+
+#[derive(Clone, Debug)]
+pub struct BMHBytesPattern<'s> {
+    pattern: &'s [u8],
+    bad_char_table: Option<Vec<usize>>,
 }
 
-pub fn bmh_search(pattern: &str, text: &str, bad_char_table: &[usize]) -> Option<usize> {
-    if pattern.len() == 0 {
-        return None;
+impl<'s> BMHBytesPattern<'s> {
+    // Anything which can be borrowed as bytes can be the pattern,
+    // but it has to outlive the `BMHBytesPattern`.
+    pub fn new<P>(pattern: &'s P) -> BMHBytesPattern<'s>
+        where P: AsRef<[u8]> + ?Sized {
+        BMHBytesPattern{
+            pattern: pattern.as_ref(),
+            bad_char_table: None,
+        }
     }
-    let text = text.as_bytes();
-    let pattern = pattern.as_bytes();
     
-    let mut t = 0;
-    // While there's enough room in the text for the pattern:
-    while t + pattern.len() <= text.len() {
-        // Starting at the end of the pattern,
-        // while the pattern matches the text,
-        // move back.
-        let mut p = pattern.len() - 1;
-        while text[t+p] == pattern[p] {
-            // If we reached the start of the pattern, return 
-            // the pattern's start position in the text.
-            if p == 0 {
-                return Some(t)    
+    pub fn bmh<T>(&mut self, text: &T) -> Option<usize>
+        where T: AsRef<[u8]> + ?Sized {
+        let pattern = self.pattern;
+        // A single byte can only ever shift by one, so it's scanned
+        // for directly, without building the table.
+        if pattern.len() == 1 {
+            return memchr::memchr(pattern[0], text.as_ref());
+        }
+        // A short pattern can't shift far, so BMH reads most of the text
+        // anyway, and it might as well first look for the pattern's
+        // rarest byte, then check the rest of the pattern's bytes turn
+        // up after it. If any are missing there's no match, and no need
+        // for the table, and otherwise BMH can start from just before
+        // the rarest byte. Long patterns skip most of the text, which
+        // the checks could read all of.
+        let mut text = text.as_ref();
+        let mut start = 0;
+        if !pattern.is_empty() && pattern.len() <= PREFILTER_MAX_LEN {
+            let (rarest, offset) = memchr::rarest_byte(pattern);
+            start = memchr::memchr(rarest, text)?.saturating_sub(offset);
+            text = &text[start..];
+            if !memchr::contains_all(text, &memchr::byte_set(pattern)) {
+                return None;
             }
-            p -= 1;
         }
-        // There was a mismatch.
-        // Shift forwards in the text so that the character
-        // in the text lines up with the last occurence
-        // of that character in the in the pattern.
-        t += bad_char_table[text[t+p] as usize];
+        let bad_char_table = self.cached_bad_char_table();
+      
+        // Search the text using the pattern and bad character table.
+        bmh_search_bytes(pattern, text, bad_char_table).map(|i| start + i)
+    }
+    
+    pub fn clear_cache(&mut self) {
+        self.bad_char_table = None;
+    }
+    
+    fn cached_bad_char_table(&mut self) -> &[usize] {
+        // Generate the bad character table using the pattern.
+        match self.bad_char_table {
+            None => {
+                self.bad_char_table = Some(bad_character_table_bytes(self.pattern));
+                &self.bad_char_table.as_ref().unwrap()[..]
+            },
+            Some(ref b) => &b[..]
+        }
     }
-    None
 }
 
-#[cfg(test)]
-mod correct_return {
-    use super::KMPPattern;
-    pub use super::BMHPattern;
+impl<'s> Searcher<u8> for BMHBytesPattern<'s> {
+    fn find(&mut self, text: &[u8]) -> Option<usize> {
+        self.bmh(text)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct BMHPattern<'s> {
+    u8_kmp: KMPPattern<'s, u8>,
+    u8_bmh: BMHBytesPattern<'s>,
     
-    pub const CASES: [(Option<usize>, &'static str); 7] = [
-        (Some(0),  "the"),
-        (Some(0),  "the dog is"),
-        (Some(1),  "he "),
-        (Some(4),  "dog"),
+    pattern: &'s str,
+    ascii_case_bad_char_table: Option<Vec<usize>>,
+    // The bad character table for searching backwards, for `rbmh`.
+    reverse_bad_char_table: Option<Vec<usize>>,
+    // The pattern's chars and their border table, for `search_chars`.
+    char_kmp: Option<(Vec<char>, Vec<usize>)>,
+}
+
+impl<'s> BMHPattern<'s> {
+    // Anything which can be borrowed as a `str` can be the pattern,
+    // e.g. a `String` or a `Cow<str>`, but it has to outlive the
+    // `BMHPattern`.
+    pub fn new<P>(pattern: &'s P) -> BMHPattern<'s>
+        where P: AsRef<str> + ?Sized {
+        let pattern = pattern.as_ref();
+        BMHPattern{
+            u8_kmp: KMPPattern::new(pattern.as_bytes()),
+            u8_bmh: BMHBytesPattern::new(pattern.as_bytes()),
+            
+            pattern,
+            ascii_case_bad_char_table: None,
+            reverse_bad_char_table: None,
+            char_kmp: None,
+        }
+    }
+    
+    // The searches which only give a byte offset work on any text
+    // which can be borrowed as bytes, even if it isn't valid UTF-8.
+    pub fn linear<T>(&self, text: &T) -> Option<usize>
+        where T: AsRef<[u8]> + ?Sized {
+        self.u8_kmp.linear(text.as_ref())
+    }
+    
+    pub fn kmp<T>(&mut self, text: &T) -> Option<usize>
+        where T: AsRef<[u8]> + ?Sized {
+        self.u8_kmp.kmp(text.as_ref())
+    }
+    
+    pub fn bmh<T>(&mut self, text: &T) -> Option<usize>
+        where T: AsRef<[u8]> + ?Sized {
+        self.u8_bmh.bmh(text)
+    }
+    
+    // BMH with a sentinel: a copy of the pattern is appended to the text,
+    // so there's always a match and the search doesn't need to check
+    // whether the window has run off the end. The text is truncated back
+    // to how it was before this returns, so it's only borrowed mutably
+    // to make room for the sentinel.
+    pub fn bmh_with_sentinel(&mut self, text: &mut Vec<u8>) -> Option<usize> {
+        let pattern = self.pattern.as_bytes();
+        // Like `str::find`, an empty pattern matches at the start.
+        if pattern.is_empty() {
+            return Some(0);
+        }
+        
+        let len = text.len();
+        text.extend_from_slice(pattern);
+        let found = sentinel_search(pattern, text, self.cached_bad_char_table());
+        text.truncate(len);
+        
+        // A match running into the sentinel isn't in the text.
+        if found + pattern.len() <= len { Some(found) } else { None }
+    }
+    
+    // Raita's variant of BMH, which shares its bad character table.
+    pub fn raita<T>(&mut self, text: &T) -> Option<usize>
+        where T: AsRef<[u8]> + ?Sized {
+        let pattern = self.pattern.as_bytes();
+        raita_search_by(pattern, text.as_ref(), self.cached_bad_char_table(), |a, b| a == b)
+    }
+    
+    pub fn contains<T>(&mut self, text: &T) -> bool
+        where T: AsRef<[u8]> + ?Sized {
+        self.bmh(text).is_some()
+    }
+    
+    // `bmh`, but an empty pattern is an error rather than
+    // a match at the start.
+    pub fn try_find(&mut self, text: &str) -> Result<Option<usize>, SearchError> {
+        if self.pattern.is_empty() {
+            return Err(SearchError::EmptyPattern);
+        }
+        Ok(self.bmh(text))
+    }
+    
+    pub fn find_match<T>(&mut self, text: &T) -> Option<Match>
+        where T: AsRef<[u8]> + ?Sized {
+        let len = self.pattern.len();
+        self.bmh(text).map(|start| Match{ start, end: start + len })
+    }
+    
+    // Each of these starts a `SearchOptions` with the given flag set.
+    pub fn case_insensitive(self, yes: bool) -> SearchOptions<'s> {
+        SearchOptions::new(self).case_insensitive(yes)
+    }
+    
+    pub fn whole_word(self, yes: bool) -> SearchOptions<'s> {
+        SearchOptions::new(self).whole_word(yes)
+    }
+    
+    pub fn overlapping(self, yes: bool) -> SearchOptions<'s> {
+        SearchOptions::new(self).overlapping(yes)
+    }
+    
+    pub fn anchored(self, yes: bool) -> SearchOptions<'s> {
+        SearchOptions::new(self).anchored(yes)
+    }
+    
+    // Searches each text with the same tables, which are only
+    // generated for the first of them.
+    pub fn find_in_each<'t, I>(&mut self, texts: I) -> Vec<Option<usize>>
+        where I: IntoIterator<Item = &'t str> {
+        texts.into_iter().map(|text| self.bmh(text)).collect()
+    }
+    
+    // Only compares the one place the pattern could be,
+    // rather than searching the whole text.
+    pub fn starts_with<T>(&self, text: &T) -> bool
+        where T: AsRef<[u8]> + ?Sized {
+        text.as_ref().get(..self.pattern.len()) == Some(self.pattern.as_bytes())
+    }
+    
+    pub fn ends_with<T>(&self, text: &T) -> bool
+        where T: AsRef<[u8]> + ?Sized {
+        let text = text.as_ref();
+        match text.len().checked_sub(self.pattern.len()) {
+            Some(start) => &text[start..] == self.pattern.as_bytes(),
+            None => false,
+        }
+    }
+    
+    // Whether the text starts with the pattern, and whether it ends
+    // with it. When the pattern is as long as the text, both are
+    // the same comparison, so it's only done once.
+    pub fn find_at_edges(&self, text: &str) -> (bool, bool) {
+        if text.len() == self.pattern.len() {
+            let whole = text == self.pattern;
+            return (whole, whole);
+        }
+        (self.starts_with(text), self.ends_with(text))
+    }
+    
+    pub fn find_from(&mut self, text: &str, mut start: usize) -> Option<usize> {
+        if start > text.len() {
+            return None;
+        }
+        // A match can only start on a char boundary, so skip
+        // ahead to the next one rather than slicing mid-char.
+        while !text.is_char_boundary(start) {
+            start += 1;
+        }
+        // Search the rest of the text, keeping offsets absolute.
+        self.bmh(&text[start..]).map(|i| start + i)
+    }
+    
+    pub fn bmh_char_index(&mut self, text: &str) -> Option<usize> {
+        // Matches start on a char boundary, so every char before
+        // the match is counted whole.
+        self.bmh(text).map(|i| text[..i].chars().count())
+    }
+    
+    // Gives the same char index as `bmh_char_index`, but in a single
+    // pass of KMP over the text's chars, without collecting them.
+    pub fn search_chars(&mut self, text: &str) -> Option<usize> {
+        if self.char_kmp.is_none() {
+            let chars = self.pattern.chars().collect::<Vec<_>>();
+            let borders = border_table(&chars);
+            self.char_kmp = Some((chars, borders));
+        }
+        let (ref pattern, ref borders) = *self.char_kmp.as_ref().unwrap();
+        
+        // Like `str::find`, an empty pattern matches at the start.
+        if pattern.is_empty() {
+            return Some(0);
+        }
+        let mut p = 0;
+        for (i, c) in text.chars().enumerate() {
+            // As in `KMPMatches`, fall back through the borders
+            // until one can be extended by `c`.
+            while p != 0 && pattern[p] != c {
+                p = borders[p - 1];
+            }
+            if pattern[p] == c {
+                p += 1;
+            }
+            if p == pattern.len() {
+                return Some(i + 1 - p);
+            }
+        }
+        None
+    }
+    
+    pub fn bmh_ignore_ascii_case(&mut self, text: &str) -> Option<usize> {
+        let pattern = self.pattern;
+        
+        // Generate a bad character table where upper and lower case
+        // letters share the shift of whichever was last in the pattern.
+        let bad_char_table: &[usize] = match self.ascii_case_bad_char_table {
+            None => {
+                let mut table = bad_character_table(pattern);
+                for (i, c) in pattern.bytes().enumerate() {
+                    let shift = pattern.len() - 1 - i;
+                    table[c.to_ascii_lowercase() as usize] = shift;
+                    table[c.to_ascii_uppercase() as usize] = shift;
+                }
+                self.ascii_case_bad_char_table = Some(table);
+                &self.ascii_case_bad_char_table.as_ref().unwrap()[..]
+            },
+            Some(ref b) => &b[..]
+        };
+        
+        // Non-ASCII bytes are left alone, so they still compare exactly.
+        bmh_search_by(pattern.as_bytes(), text.as_bytes(), bad_char_table,
+                      |a, b| a.eq_ignore_ascii_case(&b))
+    }
+    
+    pub fn find_word(&mut self, text: &str) -> Option<usize> {
+        let len = self.pattern.len();
+        let bytes = text.as_bytes();
+        let mut start = 0;
+        while let Some(i) = self.find_from(text, start) {
+            // Only ASCII bytes can be word characters, so checking
+            // the neighbouring bytes is the same as checking chars.
+            let before = i.checked_sub(1).map(|j| bytes[j] as char);
+            let after = bytes.get(i + len).map(|&b| b as char);
+            if is_word_boundary(before, after) {
+                return Some(i);
+            }
+            start = i + 1;
+        }
+        None
+    }
+    
+    pub fn find_all<'a>(&'a mut self, text: &'a str) -> impl Iterator<Item = usize> + 'a {
+        self.matches(text)
+    }
+    
+    // Like `str::match_indices`, the slices are of the text,
+    // not the pattern, so they live as long as the text does.
+    pub fn match_indices<'a, 't: 'a>(&'a mut self, text: &'t str) -> impl Iterator<Item = (usize, &'t str)> + 'a {
+        let len = self.pattern.len();
+        self.matches(text).map(move |start| (start, &text[start..start + len]))
+    }
+    
+    // Each match's start, with a slice of the text from `before` chars
+    // before the match to `after` chars after it, like `grep -o` with
+    // context. The context stops short at the edges of the text,
+    // and it's counted in chars, so it never splits one.
+    pub fn matches_with_context<'a, 't: 'a>(&'a mut self, text: &'t str, before: usize, after: usize)
+        -> impl Iterator<Item = (usize, &'t str)> + 'a {
+        let len = self.pattern.len();
+        self.matches(text).map(move |start| {
+            let end = start + len;
+            let from = text[..start].char_indices().rev().take(before).last()
+                .map_or(start, |(i, _)| i);
+            let to = text[end..].char_indices().nth(after)
+                .map_or(text.len(), |(i, _)| end + i);
+            (start, &text[from..to])
+        })
+    }
+    
+    pub fn split<'a>(&'a mut self, text: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        // An empty pattern has no matches, so the whole text is
+        // the only piece, unlike `str::split` which splits every char.
+        let len = self.pattern.len();
+        BMHSplit{
+            matches: self.matches(text),
+            len,
+            text,
+            position: Some(0),
+        }
+    }
+    
+    // The `(start, end)` byte ranges between the matches, as found
+    // by `find_all`. Unlike `split`, there are no empty ranges, so
+    // these and the matches cover the text with nothing in between.
+    pub fn non_matching_spans<'a>(&'a mut self, text: &'a str) -> impl Iterator<Item = (usize, usize)> + 'a {
+        let len = self.pattern.len();
+        let mut position = 0;
+        // `None` marks the end of the text, after the last match.
+        self.matches(text).map(Some).chain(iter::once(None)).filter_map(move |start| {
+            let span = (position, start.unwrap_or(text.len()));
+            if let Some(start) = start {
+                position = start + len;
+            }
+            if span.0 < span.1 { Some(span) } else { None }
+        })
+    }
+    
+    pub fn count(&mut self, text: &str) -> usize {
+        self.find_all(text).count()
+    }
+    
+    // `find_all`, collected. The matches are found from left to right
+    // and don't overlap, so they're sorted with no duplicates.
+    pub fn find_all_vec(&mut self, text: &str) -> Vec<usize> {
+        self.find_all(text).collect()
+    }
+    
+    // The matches are found lazily, so this stops searching
+    // as soon as it has `max` of them.
+    pub fn find_up_to(&mut self, text: &str, max: usize) -> Vec<usize> {
+        self.find_all(text).take(max).collect()
+    }
+    
+    pub fn for_each_match<F>(&mut self, text: &str, f: F)
+        where F: FnMut(usize) {
+        self.find_all(text).for_each(f)
+    }
+    
+    pub fn try_for_each_match<B, F>(&mut self, text: &str, f: F) -> ControlFlow<B>
+        where F: FnMut(usize) -> ControlFlow<B> {
+        self.find_all(text).try_for_each(f)
+    }
+    
+    pub fn rfind(&mut self, text: &str) -> Option<usize> {
+        self.u8_kmp.rfind(text.as_bytes())
+    }
+    
+    // BMH run backwards from the end of the text, so the last match
+    // is found without going through all of the others first.
+    pub fn rbmh(&mut self, text: &str) -> Option<usize> {
+        let pattern = self.pattern.as_bytes();
+        if self.reverse_bad_char_table.is_none() {
+            self.reverse_bad_char_table = Some(reverse_bad_character_table(pattern));
+        }
+        let bad_char_table = self.reverse_bad_char_table.as_ref().unwrap();
+        
+        rbmh_search(pattern, text.as_bytes(), bad_char_table)
+    }
+    
+    // Replaces the matches found by `find_all`, which don't overlap,
+    // so "aba" in "ababa" is only replaced at the start.
+    pub fn replace_all(&mut self, text: &str, replacement: &str) -> String {
+        // Unlike `str::replace`, an empty pattern has no matches,
+        // so the text is returned unchanged.
+        let len = self.pattern.len();
+        let mut replaced = String::with_capacity(text.len());
+        let mut copied = 0;
+        for start in self.find_all(text) {
+            // Copy the gap before the match, then the replacement.
+            replaced.push_str(&text[copied..start]);
+            replaced.push_str(replacement);
+            copied = start + len;
+        }
+        replaced.push_str(&text[copied..]);
+        replaced
+    }
+    
+    // Replaces every occurrence of the pattern in the original text,
+    // including those which overlap, from left to right. Overlapping
+    // occurrences each get a replacement, but the text they share is
+    // only removed once, so "aba" in "ababa" becomes two replacements
+    // with nothing left between them.
+    pub fn replace_all_overlapping(&mut self, text: &str, replacement: &str) -> String {
+        let len = self.pattern.len();
+        let mut replaced = String::with_capacity(text.len());
+        let mut copied = 0;
+        for start in self.u8_kmp.find_overlapping(text.as_bytes()) {
+            // Only copy the gap if this didn't overlap the last match.
+            if start > copied {
+                replaced.push_str(&text[copied..start]);
+            }
+            replaced.push_str(replacement);
+            copied = start + len;
+        }
+        replaced.push_str(&text[copied..]);
+        replaced
+    }
+    
+    pub fn replace_first(&mut self, text: &str, replacement: &str) -> String {
+        // This always allocates, even when there's no match.
+        // As with `replace_all`, an empty pattern has no matches.
+        let len = self.pattern.len();
+        match self.find_all(text).next() {
+            Some(start) => {
+                let mut replaced = String::with_capacity(text.len() - len + replacement.len());
+                replaced.push_str(&text[..start]);
+                replaced.push_str(replacement);
+                replaced.push_str(&text[start + len..]);
+                replaced
+            },
+            None => text.to_string(),
+        }
+    }
+    
+    pub fn clear_cache(&mut self) {
+        self.u8_kmp.clear_cache();
+        self.u8_bmh.clear_cache();
+        self.ascii_case_bad_char_table = None;
+        self.reverse_bad_char_table = None;
+        self.char_kmp = None;
+    }
+    
+    // Builds the bad character table used by `bmh` now rather than
+    // on the first search, like `KMPPattern::preprocess`.
+    pub fn preprocess(&mut self) {
+        self.cached_bad_char_table();
+    }
+    
+    // Builds the bad character table, if it isn't already, and keeps
+    // only that, so the pattern can be searched for through `&self`.
+    pub fn compile(mut self) -> CompiledBmh<'s> {
+        self.preprocess();
+        CompiledBmh{
+            pattern: self.pattern,
+            bad_char_table: self.u8_bmh.bad_char_table.take().unwrap(),
+        }
+    }
+    
+    fn matches<'a>(&'a mut self, text: &'a str) -> BMHMatches<'a> {
+        let pattern = self.pattern;
+        let bad_char_table = self.cached_bad_char_table();
+        
+        BMHMatches{
+            pattern,
+            bad_char_table,
+            text,
+            position: 0,
+        }
+    }
+    
+    fn cached_bad_char_table(&mut self) -> &[usize] {
+        self.u8_bmh.cached_bad_char_table()
+    }
+}
+
+impl<'s> From<&'s str> for BMHPattern<'s> {
+    fn from(pattern: &'s str) -> BMHPattern<'s> {
+        BMHPattern::new(pattern)
+    }
+}
+
+// The pattern is valid UTF-8, but the text doesn't have to be.
+impl<'s> Searcher<u8> for BMHPattern<'s> {
+    fn find(&mut self, text: &[u8]) -> Option<usize> {
+        self.u8_bmh.bmh(text)
+    }
+}
+
+// A `BMHPattern` with its table already built, so it never needs
+// to be mutated and can be shared between threads, e.g. in an `Arc`.
+#[derive(Clone, Debug)]
+pub struct CompiledBmh<'s> {
+    pattern: &'s str,
+    bad_char_table: Vec<usize>,
+}
+
+impl<'s> CompiledBmh<'s> {
+    pub fn find(&self, text: &str) -> Option<usize> {
+        bmh_search(self.pattern, text, &self.bad_char_table)
+    }
+}
+
+struct BMHMatches<'a> {
+    pattern: &'a str,
+    bad_char_table: &'a [usize],
+    text: &'a str,
+    // Where in the text to resume searching from.
+    position: usize,
+}
+
+impl<'a> Iterator for BMHMatches<'a> {
+    type Item = usize;
+    
+    fn next(&mut self) -> Option<usize> {
+        if self.pattern.is_empty() || self.position > self.text.len() {
+            return None;
+        }
+        // Matches always end on a char boundary, so this slice is safe.
+        match bmh_search(self.pattern, &self.text[self.position..], self.bad_char_table) {
+            Some(i) => {
+                // Matches don't overlap, so skip the whole match.
+                let start = self.position + i;
+                self.position = start + self.pattern.len();
+                Some(start)
+            },
+            None => {
+                self.position = self.text.len() + 1;
+                None
+            },
+        }
+    }
+}
+
+struct BMHSplit<'a> {
+    matches: BMHMatches<'a>,
+    len: usize,
+    text: &'a str,
+    // Where the next piece starts, or `None` once the last
+    // piece has been returned.
+    position: Option<usize>,
+}
+
+impl<'a> Iterator for BMHSplit<'a> {
+    type Item = &'a str;
+    
+    fn next(&mut self) -> Option<&'a str> {
+        let position = self.position?;
+        match self.matches.next() {
+            // The piece runs up to the next match.
+            Some(start) => {
+                self.position = Some(start + self.len);
+                Some(&self.text[position..start])
+            },
+            // After the last match, the rest of the text is the last piece.
+            None => {
+                self.position = None;
+                Some(&self.text[position..])
+            },
+        }
+    }
+}
+
+// Whether a match between these characters is a whole word,
+// where `None` is the edge of the text.
+fn is_word_boundary(before: Option<char>, after: Option<char>) -> bool {
+    !before.is_some_and(is_word_char) && !after.is_some_and(is_word_char)
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Algorithm {
+    Linear,
+    Kmp,
+    Bmh,
+    // BMH for patterns of at least `AUTO_BMH_MIN_LEN` bytes, KMP otherwise.
+    Auto,
+}
+
+// BMH can skip at most the length of the pattern at a time, so for
+// shorter patterns its table isn't worth building and KMP is used.
+pub const AUTO_BMH_MIN_LEN: usize = 4;
+
+pub fn search(algorithm: Algorithm, pattern: &str, text: &str) -> Option<usize> {
+    search_bytes(algorithm, pattern.as_bytes(), text.as_bytes())
+}
+
+// Like `str::find`, but for bytes, choosing the algorithm
+// as `Algorithm::Auto` does.
+pub fn find(pattern: &[u8], text: &[u8]) -> Option<usize> {
+    search_bytes(Algorithm::Auto, pattern, text)
+}
+
+// Some searches give byte offsets into a `str`, and others, like a
+// `KMPPattern<char>`, give char offsets, so these convert between them.
+// The count of chars or bytes in the text is the offset of its end.
+pub fn byte_to_char_index(text: &str, byte: usize) -> Option<usize> {
+    // This is also false past the end of the text.
+    if !text.is_char_boundary(byte) {
+        return None;
+    }
+    Some(text[..byte].chars().count())
+}
+
+pub fn char_to_byte_index(text: &str, ch: usize) -> Option<usize> {
+    text.char_indices().map(|(i, _)| i)
+        .chain(iter::once(text.len()))
+        .nth(ch)
+}
+
+fn search_bytes(algorithm: Algorithm, pattern: &[u8], text: &[u8]) -> Option<usize> {
+    let algorithm = match algorithm {
+        Algorithm::Auto if pattern.len() >= AUTO_BMH_MIN_LEN => Algorithm::Bmh,
+        Algorithm::Auto => Algorithm::Kmp,
+        algorithm => algorithm,
+    };
+    match algorithm {
+        Algorithm::Linear => linear_search(pattern, text),
+        Algorithm::Kmp => kmp_search(pattern, text, &border_table(pattern)),
+        Algorithm::Bmh | Algorithm::Auto =>
+            bmh_search_bytes(pattern, text, &bad_character_table_bytes(pattern)),
+    }
+}
+
+pub fn linear_search<C>(pattern: &[C], text: &[C]) -> Option<usize>
+    where C: PartialEq {
+    linear_search_by(pattern, text, |a, b| a == b)
+}
+
+// `linear_search`, but also returns how many times
+// an element of the pattern was compared with the text.
+pub fn linear_search_counted<C>(pattern: &[C], text: &[C]) -> (Option<usize>, usize)
+    where C: PartialEq {
+    let mut comparisons = 0;
+    let found = linear_search_by(pattern, text, |a, b| {
+        comparisons += 1;
+        a == b
+    });
+    (found, comparisons)
+}
+
+// `linear_search`, but with each pair of elements compared by `eq`,
+// e.g. to ignore case, or compare only some fields. `eq` is given
+// the text's element first, then the pattern's.
+pub fn linear_search_by<C, F>(pattern: &[C], text: &[C], mut eq: F) -> Option<usize>
+    where F: FnMut(&C, &C) -> bool {
+    
+    // Like `str::find`, an empty pattern matches at the start,
+    // even when the text is empty.
+    if pattern.is_empty() {
+        return Some(0);
+    }
+    
+    // A pattern longer than the text can't fit anywhere in it.
+    if pattern.len() > text.len() {
+        return None;
+    }
+    
+    // For each starting point in the text where the whole
+    // pattern fits:
+    'text:
+    for i_text in 0..=text.len() - pattern.len() {
+        // For each character in the pattern:
+        for i_pattern in 0..pattern.len() {
+            // If there is a mismatch, try the next position.
+            if !eq(&text[i_text + i_pattern], &pattern[i_pattern]) {
+                continue 'text;
+            }
+        }
+        // If we went through the whole pattern with no mismatch,
+        // we found the first instance of the pattern.
+        return Some(i_text);
+    }
+    None
+}
+
+// Finds the pattern in the text ignoring case, by lowercasing both
+// with `char::to_lowercase`. Lowercasing can change how many chars there
+// are, so the result is a char index into the original text.
+// This isn't full case folding, so "SS" doesn't match "ß", and it isn't
+// locale aware, so 'I' only matches 'i', never the Turkish dotless 'ı'.
+// A match has to cover whole chars of the text, so "i" doesn't match
+// 'İ', which lowercases to an 'i' followed by a combining dot.
+pub fn fold_case_search(pattern: &str, text: &str) -> Option<usize> {
+    let pattern = pattern.chars().flat_map(char::to_lowercase).collect::<Vec<_>>();
+    // Like `str::find`, an empty pattern matches at the start.
+    if pattern.is_empty() {
+        return Some(0);
+    }
+    
+    let text = text.chars().collect::<Vec<_>>();
+    (0..text.len()).find(|&start| starts_with_folded(&text[start..], &pattern))
+}
+
+// Whether the start of the text lowercases to the lowercased pattern,
+// ending on a char of the text.
+fn starts_with_folded(text: &[char], pattern: &[char]) -> bool {
+    let mut p = 0;
+    for c in text {
+        for lower in c.to_lowercase() {
+            if p == pattern.len() || lower != pattern[p] {
+                return false;
+            }
+            p += 1;
+        }
+        if p == pattern.len() {
+            return true;
+        }
+    }
+    false
+}
+
+pub fn border_table<C>(pattern: &[C]) -> Vec<usize>
+    where C: PartialEq {
+        
+    // `borders[i]` is the length of the longest proper border
+    // of the prefix of length `i + 1`.
+    let mut borders = Vec::with_capacity(pattern.len());
+    if pattern.is_empty() {
+        return borders;
+    }
+  
+    // A single character has no proper border.
+    borders.push(0);
+    
+    // For each prefix `p` of length `i` in the pattern,
+    // followed by the character `c`,
+    // starting with the prefix of length 1:
+    for (i, c) in pattern.iter().enumerate().skip(1) {
+        // Starting with longest border of p,
+        // keep checking the current border's longest border until
+        // (`the prefix of length b`+`c`) is a border of (`p`+`c`),
+        // or there are no more borders to check.
+        let mut b = borders[i - 1];
+        while pattern[b] != *c && b != 0 {
+            b = borders[b - 1]
+        }
+        // If an extensible border was found, extend it,
+        // otherwise this prefix has no border.
+        if pattern[b] == *c {
+            borders.push(b + 1);
+        } else {
+            borders.push(0);
+        }
+    }
+    assert_eq!(pattern.len(), borders.len());
+    
+    borders
+}
+
+pub fn kmp_search<C>(pattern: &[C], text: &[C], borders: &[usize]) -> Option<usize>
+    where C: PartialEq {
+    kmp_search_by(pattern, text, borders, |a, b| a == b)
+}
+
+// `kmp_search`, but also returns how many times
+// an element of the pattern was compared with the text.
+pub fn kmp_search_counted<C>(pattern: &[C], text: &[C], borders: &[usize]) -> (Option<usize>, usize)
+    where C: PartialEq {
+    let mut comparisons = 0;
+    let found = kmp_search_by(pattern, text, borders, |a, b| {
+        comparisons += 1;
+        a == b
+    });
+    (found, comparisons)
+}
+
+// `kmp_search` over a text which is only available an element at a
+// time, e.g. `str::chars`. KMP never needs to look back at the text,
+// so nothing is collected, and the result is the index of the
+// element the first match starts at.
+pub fn kmp_search_iter<C, I>(pattern: &[C], text: I) -> Option<usize>
+    where C: PartialEq, I: IntoIterator<Item = C> {
+    // Like `str::find`, an empty pattern matches at the start.
+    if pattern.is_empty() {
+        return Some(0);
+    }
+    
+    let mut automaton = KmpAutomaton::new(pattern);
+    text.into_iter().position(|c| automaton.step(c))
+        .map(|end| end + 1 - pattern.len())
+}
+
+// `kmp_search`, but with each pair of elements compared by `eq`.
+fn kmp_search_by<C, F>(pattern: &[C], text: &[C], borders: &[usize], mut eq: F) -> Option<usize>
+    where F: FnMut(&C, &C) -> bool {
+    // Like `str::find`, an empty pattern matches at the start.
+    if pattern.is_empty() {
+        return Some(0);
+    }
+    
+    // A pattern longer than the text can't fit anywhere in it.
+    if pattern.len() > text.len() {
+        return None;
+    }
+    
+    let mut t = 0;
+    let mut p = 0;
+    // While we haven't reached the last possible starting point
+    // for the pattern in the text.
+    while t+p < text.len() {
+        // If there is a match, move forward in the pattern.
+        if eq(&text[t+p], &pattern[p]) {
+            p += 1;
+            // If we reached the end of the pattern, return 
+            // the substring's starting position in the text.
+            if p == pattern.len() {
+                return Some(t)    
+            }
+            continue;
+        }
+       
+        // There was a mismatch.
+        // If we're at the beginning of the pattern, shift by one.
+        // Otherwise, shift so that the previous characters form
+        // the longest possible prefix of the pattern, then recheck
+        // this character.
+        if p == 0 {
+            t += 1;
+        } else {
+            t += p - borders[p - 1];
+            p = borders[p - 1];
+        }
+    }
+    None
+}
+
+// The same as `border_table`, but for the pattern read backwards,
+// so `borders[i]` is the longest proper border of the last `i + 1`
+// characters read from right to left.
+fn reverse_border_table<C>(pattern: &[C]) -> Vec<usize>
+    where C: PartialEq {
+    
+    let n = pattern.len();
+    let mut borders = Vec::with_capacity(n);
+    if n == 0 {
+        return borders;
+    }
+    borders.push(0);
+    
+    for i in 1..n {
+        let c = &pattern[n - 1 - i];
+        let mut b = borders[i - 1];
+        while pattern[n - 1 - b] != *c && b != 0 {
+            b = borders[b - 1];
+        }
+        if pattern[n - 1 - b] == *c {
+            borders.push(b + 1);
+        } else {
+            borders.push(0);
+        }
+    }
+    
+    borders
+}
+
+// Finds the start of the last match by running KMP from the end of
+// the text towards the start, with the pattern read backwards.
+fn kmp_rsearch<C>(pattern: &[C], text: &[C], reverse_borders: &[usize]) -> Option<usize>
+    where C: PartialEq {
+    
+    let n = pattern.len();
+    let mut p = 0;
+    for (t, c) in text.iter().enumerate().rev() {
+        // Fall back through the borders until `c` extends one.
+        while pattern[n - 1 - p] != *c && p != 0 {
+            p = reverse_borders[p - 1];
+        }
+        if pattern[n - 1 - p] == *c {
+            p += 1;
+        }
+        // The match is complete when we reach the pattern's start.
+        if p == n {
+            return Some(t);
+        }
+    }
+    None
+}
+
+pub fn bad_character_table(pattern: &str) -> Vec<usize> {
+    bad_character_table_bytes(pattern.as_bytes())
+}
+
+pub fn bad_character_table_bytes(pattern: &[u8]) -> Vec<usize> {
+    // If the character doesn't appear in the pattern,
+    // then we can skip ahead by the length of the whole
+    // pattern if the character appears in the text.
+    let mut bad_char_table =
+        (0..=u8::MAX).map(|_| -> usize { pattern.len() })
+                         .collect::<Vec<_>>();
+   
+    // Otherwise we should skip ahead by the distance between the
+    // end of the pattern and the last occurence of that character.
+    for (i, &c) in pattern.iter().enumerate() {
+        bad_char_table[c as usize] = pattern.len() - 1 - i;
+    }
+    
+    bad_char_table
+}
+
+// The mirror image of `bad_character_table_bytes`, for searching
+// backwards: how far the pattern can be shifted left to line up the
+// first occurrence of each character after the pattern's start.
+fn reverse_bad_character_table(pattern: &[u8]) -> Vec<usize> {
+    let mut bad_char_table = vec![pattern.len(); 256];
+    for (i, &c) in pattern.iter().enumerate().skip(1).rev() {
+        bad_char_table[c as usize] = i;
+    }
+    bad_char_table
+}
+
+// `bmh_search_bytes` with the text and the pattern both read backwards,
+// so windows are compared from the start and shifted by the character
+// at the start of the window.
+fn rbmh_search(pattern: &[u8], text: &[u8], bad_char_table: &[usize]) -> Option<usize> {
+    // Like `str::rfind`, an empty pattern matches at the end.
+    if pattern.is_empty() {
+        return Some(text.len());
+    }
+    if pattern.len() > text.len() {
+        return None;
+    }
+    
+    let last = pattern.len() - 1;
+    let mut t = text.len() - pattern.len();
+    loop {
+        let mut p = 0;
+        while text[t+p] == pattern[p] {
+            if p == last {
+                return Some(t);
+            }
+            p += 1;
+        }
+        // Stop once the window would start before the text.
+        let shift = bad_char_table[text[t] as usize];
+        if shift > t {
+            return None;
+        }
+        t -= shift;
+    }
+}
+
+// Horspool's table as it's usually given, which leaves out the last
+// character of the pattern, so every entry is at least one.
+// `bad_character_table_bytes` includes it, so the last character's
+// entry is zero, but the search never shifts by less than one.
+pub fn bad_character_table_horspool(pattern: &[u8]) -> Vec<usize> {
+    let mut bad_char_table = vec![pattern.len(); 256];
+    for (i, &c) in pattern.iter().enumerate().take(pattern.len().saturating_sub(1)) {
+        bad_char_table[c as usize] = pattern.len() - 1 - i;
+    }
+    bad_char_table
+}
+
+pub fn bmh_search(pattern: &str, text: &str, bad_char_table: &[usize]) -> Option<usize> {
+    bmh_search_bytes(pattern.as_bytes(), text.as_bytes(), bad_char_table)
+}
+
+pub fn bmh_search_bytes(pattern: &[u8], text: &[u8], bad_char_table: &[usize]) -> Option<usize> {
+    bmh_skip_search_by(pattern, text, bad_char_table, |a, b| a == b)
+}
+
+// `bmh_search`, but also returns how many times a byte of the text
+// was compared, both with the pattern, and by the scan for the last
+// byte of the pattern which skips windows.
+pub fn bmh_search_counted(pattern: &str, text: &str, bad_char_table: &[usize]) -> (Option<usize>, usize) {
+    let mut compared = 0;
+    let mut scanned = 0;
+    let found = bmh_skip_search_traced_by(pattern.as_bytes(), text.as_bytes(), bad_char_table, |a, b| {
+        compared += 1;
+        a == b
+    }, |step| match step {
+        BmhStep::Skip(_, read) | BmhStep::Exhausted(read) => scanned += read,
+        BmhStep::Shift(_) => {},
+    });
+    (found, compared + scanned)
+}
+
+// `bmh_search`, but also returns where each window started, after the
+// first, to show how far each move went, whether it was a shift or
+// a skip by the scan for the last byte of the pattern.
+pub fn bmh_search_traced(pattern: &str, text: &str, bad_char_table: &[usize]) -> (Option<usize>, Vec<usize>) {
+    let mut windows = Vec::new();
+    let mut at = 0;
+    let found = bmh_skip_search_traced_by(pattern.as_bytes(), text.as_bytes(), bad_char_table, |a, b| a == b, |step| {
+        if let BmhStep::Shift(t) | BmhStep::Skip(t, _) = step {
+            // The scan can find the byte where the window already is.
+            if t != at {
+                windows.push(t);
+                at = t;
+            }
+        }
+    });
+    (found, windows)
+}
+
+// `bmh_search`, but with each pair of bytes compared by `eq`.
+fn bmh_search_by<F>(pattern: &[u8], text: &[u8], bad_char_table: &[usize], mut eq: F) -> Option<usize>
+    where F: FnMut(u8, u8) -> bool {
+    
+    // Like `str::find`, an empty pattern matches at the start.
+    if pattern.is_empty() {
+        return Some(0);
+    }
+    
+    let mut t = 0;
+    // While there's enough room in the text for the pattern:
+    while t + pattern.len() <= text.len() {
+        // Starting at the end of the pattern,
+        // while the pattern matches the text,
+        // move back.
+        let mut p = pattern.len() - 1;
+        while eq(text[t+p], pattern[p]) {
+            // If we reached the start of the pattern, return 
+            // the pattern's start position in the text.
+            if p == 0 {
+                return Some(t)    
+            }
+            p -= 1;
+        }
+        // There was a mismatch.
+        // Shift forwards in the text so that the character
+        // in the text lines up with the last occurence
+        // of that character in the pattern.
+        // If that occurence is after the mismatch, shifting
+        // to it would mean going backwards, so shift by one.
+        t += bad_character_shift(bad_char_table, text[t+p], pattern.len() - 1 - p);
+    }
+    None
+}
+
+// `bmh_search_by`, but before each window is compared the text is
+// scanned for the pattern's last byte, so runs of windows which can't
+// match are skipped in one go. The scan compares bytes exactly,
+// so `eq` has to agree with `==`.
+fn bmh_skip_search_by<F>(pattern: &[u8], text: &[u8], bad_char_table: &[usize], eq: F) -> Option<usize>
+    where F: FnMut(u8, u8) -> bool {
+    bmh_skip_search_traced_by(pattern, text, bad_char_table, eq, |_| {})
+}
+
+// How the window moved in `bmh_skip_search_traced_by`.
+enum BmhStep {
+    // The bad character rule shifted the window to start here.
+    Shift(usize),
+    // The scan found a window starting here which ends with the last
+    // byte of the pattern, after reading this many bytes.
+    Skip(usize, usize),
+    // The scan read this many bytes to the end of the text without
+    // finding the last byte of the pattern.
+    Exhausted(usize),
+}
+
+// `bmh_skip_search_by`, but telling `step` each time the window moves,
+// for counting and tracing the search which `bmh_search` actually does.
+fn bmh_skip_search_traced_by<F, G>(pattern: &[u8], text: &[u8], bad_char_table: &[usize],
+                                   mut eq: F, mut step: G) -> Option<usize>
+    where F: FnMut(u8, u8) -> bool, G: FnMut(BmhStep) {
+    
+    // Like `str::find`, an empty pattern matches at the start.
+    if pattern.is_empty() {
+        return Some(0);
+    }
+    
+    let last = pattern.len() - 1;
+    let mut t = 0;
+    while t + pattern.len() <= text.len() {
+        // Jump to the next window which ends with the right byte.
+        let rest = &text[t + last..];
+        match memchr::memchr(pattern[last], rest) {
+            Some(i) => {
+                t += i;
+                step(BmhStep::Skip(t, i + 1));
+            },
+            None => {
+                step(BmhStep::Exhausted(rest.len()));
+                return None;
+            },
+        }
+        
+        // Compare the rest of the window as in `bmh_search_by`.
+        let mut p = last;
+        while eq(text[t+p], pattern[p]) {
+            if p == 0 {
+                return Some(t)
+            }
+            p -= 1;
+        }
+        t += bad_character_shift(bad_char_table, text[t+p], last - p);
+        step(BmhStep::Shift(t));
+    }
+    None
+}
+
+// `bmh_search`, for a text which is known to end with the pattern,
+// so the search always stops before the window runs off the end.
+fn sentinel_search(pattern: &[u8], text: &[u8], bad_char_table: &[usize]) -> usize {
+    let last = pattern.len() - 1;
+    let mut t = 0;
+    loop {
+        let mut p = last;
+        while text[t+p] == pattern[p] {
+            if p == 0 {
+                return t;
+            }
+            p -= 1;
+        }
+        t += bad_character_shift(bad_char_table, text[t+p], last - p);
+    }
+}
+
+// Horspool's search as it's usually given, with a table from
+// `bad_character_table_horspool`. Whatever the mismatch was, this
+// shifts by the character at the end of the window, while `bmh_search`
+// shifts by the mismatched character, allowing for how far it is from
+// the end. Both are correct, but they can visit different windows.
+pub fn horspool_search(pattern: &[u8], text: &[u8], bad_char_table: &[usize]) -> Option<usize> {
+    horspool_search_by(pattern, text, bad_char_table, |a, b| a == b)
+}
+
+// `horspool_search`, but with each pair of bytes compared by `eq`.
+fn horspool_search_by<F>(pattern: &[u8], text: &[u8], bad_char_table: &[usize], mut eq: F) -> Option<usize>
+    where F: FnMut(u8, u8) -> bool {
+    
+    // Like `str::find`, an empty pattern matches at the start.
+    if pattern.is_empty() {
+        return Some(0);
+    }
+    
+    let last = pattern.len() - 1;
+    let mut t = 0;
+    while t + pattern.len() <= text.len() {
+        let mut p = last;
+        while eq(text[t+p], pattern[p]) {
+            if p == 0 {
+                return Some(t)
+            }
+            p -= 1;
+        }
+        t += bad_char_table[text[t + last] as usize];
+    }
+    None
+}
+
+// Raita's variant of `bmh_search_by`, which compares the last, first
+// and middle characters of each window before the rest of it, since
+// near misses often match at the end but not in the middle.
+fn raita_search_by<F>(pattern: &[u8], text: &[u8], bad_char_table: &[usize], mut eq: F) -> Option<usize>
+    where F: FnMut(u8, u8) -> bool {
+    
+    // Like `str::find`, an empty pattern matches at the start.
+    if pattern.is_empty() {
+        return Some(0);
+    }
+    
+    let last = pattern.len() - 1;
+    let middle = last / 2;
+    let mut t = 0;
+    while t + pattern.len() <= text.len() {
+        let window = &text[t..t + pattern.len()];
+        if eq(window[last], pattern[last])
+            && eq(window[0], pattern[0])
+            && eq(window[middle], pattern[middle])
+            && window.iter().zip(pattern).all(|(&a, &b)| eq(a, b)) {
+            return Some(t);
+        }
+        // The probes aren't in order, so shift by the last character
+        // of the window, as if the mismatch was there.
+        t += bad_character_shift(bad_char_table, window[last], 0);
+    }
+    None
+}
+
+// How far to shift the pattern when `c` mismatched the pattern
+// character `from_end` places before the end of the pattern.
+fn bad_character_shift(bad_char_table: &[usize], c: u8, from_end: usize) -> usize {
+    cmp::max(bad_char_table[c as usize].saturating_sub(from_end), 1)
+}
+
+#[cfg(test)]
+mod correct_return {
+    use std::boxed::Box;
+    use std::string::String;
+    use std::vec::Vec;
+    use super::{linear_search, border_table, kmp_search, bmh_search, bad_character_table};
+    use super::{byte_to_char_index, char_to_byte_index};
+    use super::{bad_character_table_horspool, horspool_search, horspool_search_by};
+    use super::{Searcher, LinearPattern, KMPPattern, BMHBytesPattern, RabinKarpPattern, SundayPattern};
+    use super::BoyerMoorePattern;
+    use super::Match;
+    use super::{KmpAutomaton, StreamResult, PreprocessedKmp, IncrementalKmp};
+    use super::{search, Algorithm};
+    use core::cmp;
+    use core::ops::ControlFlow;
+    use std::cell::Cell;
+    use proptest::prelude::*;
+    use super::{bad_character_table_bytes, bad_character_shift, bmh_search_by, bmh_skip_search_by};
+    use super::{linear_search_counted, kmp_search_counted, bmh_search_counted};
+    pub use super::BMHPattern;
+    
+    pub const CASES: [(Option<usize>, &str); 9] = [
+        (Some(0),  "the"),
+        (Some(0),  "the dog is"),
+        (Some(1),  "he "),
+        (Some(4),  "dog"),
         (Some(16), "dead"),
         (Some(21), "then"),
         (None,     "frank"),
+        (None,     "dead then!!"),
+        (None,     "then!"),
     ];
-    pub const TEXT: &'static str = "the dog is very dead then";
+    pub const TEXT: &str = "the dog is very dead then";
+
+    // A small xorshift generator, so the random tests are repeatable.
+    pub struct Random(pub u64);
+
+    impl Random {
+        pub fn below(&mut self, n: u64) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0 % n
+        }
+
+        // Bytes from a small alphabet, so matches are common.
+        pub fn bytes(&mut self, max_len: u64, alphabet: u8) -> Vec<u8> {
+            let len = self.below(max_len + 1);
+            (0..len).map(|_| b'a' + self.below(alphabet as u64) as u8).collect()
+        }
+        
+        // Bytes from the whole range, most of which aren't valid UTF-8.
+        pub fn any_bytes(&mut self, max_len: u64) -> Vec<u8> {
+            let len = self.below(max_len + 1);
+            (0..len).map(|_| self.below(256) as u8).collect()
+        }
+    }
+    
+    #[test]
+    fn linear() {
+        let text = TEXT.chars().collect::<Vec<_>>();
+        for &(want, pattern) in CASES.iter() {
+            let chars = pattern.chars().collect::<Vec<_>>();
+            let searcher = KMPPattern::new(&chars[..]);
+            assert_eq!(searcher.linear(&text[..]), want);
+        }
+    }
+
+    #[test]
+    fn counted() {
+        for &(want, pattern) in CASES.iter() {
+            let bytes = pattern.as_bytes();
+            assert_eq!(linear_search_counted(bytes, TEXT.as_bytes()).0, want);
+            assert_eq!(kmp_search_counted(bytes, TEXT.as_bytes(), &border_table(bytes)).0, want);
+            assert_eq!(bmh_search_counted(pattern, TEXT, &bad_character_table(pattern)).0, want);
+        }
+        
+        // Linear search compares the run of "a"s again from each start,
+        // but KMP never goes back in the text.
+        let pattern = "aaab".as_bytes();
+        let text = "aaaaaaaaaab".as_bytes();
+        let (linear, linear_comparisons) = linear_search_counted(pattern, text);
+        let (kmp, kmp_comparisons) = kmp_search_counted(pattern, text, &border_table(pattern));
+        assert_eq!(linear, Some(7));
+        assert_eq!(kmp, linear);
+        assert_eq!(linear_comparisons, 32);
+        assert_eq!(kmp_comparisons, 18);
+        assert!(kmp_comparisons < linear_comparisons);
+        
+        assert_eq!(linear_search_counted(b"", b"abc"), (Some(0), 0));
+        // The scan reads all three bytes to find the "c",
+        // which is then compared with the pattern.
+        assert_eq!(bmh_search_counted("c", "abc", &bad_character_table("c")), (Some(2), 4));
+        // The scan reads the whole text, and nothing is compared.
+        assert_eq!(bmh_search_counted("xyz", TEXT, &bad_character_table("xyz")), (None, TEXT.len() - 2));
+    }
+    
+    #[test]
+    fn linear_search_by() {
+        let text = TEXT.chars().collect::<Vec<_>>();
+        let pattern = "THE".chars().collect::<Vec<_>>();
+        let ignore_case = |a: &char, b: &char| a.eq_ignore_ascii_case(b);
+        assert_eq!(super::linear_search_by(&pattern, &text, ignore_case), Some(0));
+        let pattern = "THEN".chars().collect::<Vec<_>>();
+        assert_eq!(super::linear_search_by(&pattern, &text, ignore_case), Some(21));
+        assert_eq!(linear_search(&pattern, &text), None);
+        
+        // Only part of each element is compared.
+        let pairs = [(1, 'a'), (2, 'b'), (3, 'c')];
+        assert_eq!(super::linear_search_by(&[(0, 'b'), (0, 'c')], &pairs, |a, b| a.1 == b.1), Some(1));
+    }
+    
+    #[test]
+    fn linear_whole_text() {
+        assert_eq!(linear_search(TEXT.as_bytes(), TEXT.as_bytes()), Some(0));
+        assert_eq!(linear_search(b"a", b"a"), Some(0));
+        assert_eq!(linear_search(b"ab", b"a"), None);
+        assert_eq!(linear_search(b"ba", b"ab"), None);
+    }
+    
+    #[test]
+    fn index_conversions() {
+        // 'é' is two bytes and '日' is three.
+        let text = "café 日本";
+        let chars = [(0, 0), (1, 1), (3, 3), (4, 5), (5, 6), (6, 9), (7, 12)];
+        for &(ch, byte) in chars.iter() {
+            assert_eq!(char_to_byte_index(text, ch), Some(byte));
+            assert_eq!(byte_to_char_index(text, byte), Some(ch));
+        }
+        // Inside a char, or past the end.
+        assert_eq!(byte_to_char_index(text, 4), None);
+        assert_eq!(byte_to_char_index(text, 7), None);
+        assert_eq!(byte_to_char_index(text, 13), None);
+        assert_eq!(char_to_byte_index(text, 8), None);
+        assert_eq!(byte_to_char_index("", 0), Some(0));
+        assert_eq!(char_to_byte_index("", 0), Some(0));
+        
+        // A byte offset from BMH and a char offset from KMP agree.
+        let chars = text.chars().collect::<Vec<_>>();
+        let pattern = "日".chars().collect::<Vec<_>>();
+        let byte = BMHPattern::new("日").bmh(text).unwrap();
+        let ch = KMPPattern::new(&pattern[..]).kmp(&chars[..]).unwrap();
+        assert_eq!(byte_to_char_index(text, byte), Some(ch));
+        assert_eq!(char_to_byte_index(text, ch), Some(byte));
+    }
+    
+    #[test]
+    fn fold_case_search() {
+        for &(want, pattern) in CASES.iter() {
+            assert_eq!(super::fold_case_search(&pattern.to_uppercase(), TEXT), want);
+        }
+        
+        // Offsets count chars, not bytes.
+        assert_eq!(super::fold_case_search("ÉCOLE", "à l'école"), Some(4));
+        assert_eq!(super::fold_case_search("école", "À L'ÉCOLE"), Some(4));
+        assert_eq!(super::fold_case_search("e", "é"), None);
+        
+        // 'ß' has no single char uppercase except 'ẞ',
+        // and isn't expanded to "ss".
+        assert_eq!(super::fold_case_search("STRAẞE", "die straße"), Some(4));
+        assert_eq!(super::fold_case_search("STRASSE", "die straße"), None);
+        
+        // Turkish dotted and dotless i aren't special cased.
+        assert_eq!(super::fold_case_search("I", "ıi"), Some(1));
+        assert_eq!(super::fold_case_search("ı", "I"), None);
+        // 'İ' lowercases to "i\u{307}", which a match has to cover all of.
+        assert_eq!(super::fold_case_search("İ", "i\u{307}"), Some(0));
+        assert_eq!(super::fold_case_search("i", "İ"), None);
+        
+        assert_eq!(super::fold_case_search("", ""), Some(0));
+        assert_eq!(super::fold_case_search("a", ""), None);
+    }
+    
+    #[test]
+    fn kmp() {
+        let text = TEXT.chars().collect::<Vec<_>>();
+        for &(want, pattern) in CASES.iter() {
+            let chars = pattern.chars().collect::<Vec<_>>();
+            let mut searcher = KMPPattern::new(&chars[..]);
+            assert_eq!(searcher.kmp(&text[..]), want);
+        }
+    }
+   
+    #[test]
+    fn empty_pattern() {
+        // Every algorithm finds the empty pattern at the start,
+        // just like `str::find`.
+        for &text in ["", "a", TEXT].iter() {
+            let want = text.find("");
+            let bytes = text.as_bytes();
+            let chars = text.chars().collect::<Vec<_>>();
+            
+            let empty: [char; 0] = [];
+            let mut searcher = KMPPattern::new(&empty[..]);
+            assert_eq!(searcher.linear(&chars[..]), want);
+            assert_eq!(searcher.kmp(&chars[..]), want);
+            
+            let mut searcher = BMHPattern::new("");
+            assert_eq!(searcher.linear(text), want);
+            assert_eq!(searcher.kmp(text), want);
+            assert_eq!(searcher.bmh(text), want);
+            
+            assert_eq!(linear_search(&[], bytes), want);
+            assert_eq!(kmp_search(&[], bytes, &[]), want);
+            assert_eq!(bmh_search("", text, &bad_character_table("")), want);
+        }
+    }
+    
+    #[test]
+    fn empty_text() {
+        // Only the empty pattern is in the empty text, and it's there
+        // just once, so every search either finds it at the start,
+        // or, for the ones which skip it, finds nothing at all.
+        let empty_chars: [char; 0] = [];
+        let patterns = CASES.iter().map(|&(_, pattern)| pattern).chain(["a", "ab", "\u{e9}"].iter().cloned());
+        for pattern in patterns {
+            let want = "".find(pattern);
+            let bytes = pattern.as_bytes();
+            let chars = pattern.chars().collect::<Vec<_>>();
+            
+            let mut searcher = KMPPattern::new(&chars[..]);
+            assert_eq!(searcher.linear(&empty_chars), want, "{:?}", pattern);
+            assert_eq!(searcher.kmp(&empty_chars), want, "{:?}", pattern);
+            assert_eq!(searcher.rfind(&empty_chars), want, "{:?}", pattern);
+            assert_eq!(searcher.find_overlapping(&empty_chars).count(), 0);
+            assert_eq!(searcher.longest_partial_suffix_match(&empty_chars), 0);
+            
+            let mut searcher = BMHPattern::new(pattern);
+            assert_eq!(searcher.linear(""), want, "{:?}", pattern);
+            assert_eq!(searcher.kmp(""), want, "{:?}", pattern);
+            assert_eq!(searcher.bmh(""), want, "{:?}", pattern);
+            assert_eq!(searcher.raita(""), want, "{:?}", pattern);
+            assert_eq!(searcher.bmh_with_sentinel(&mut Vec::new()), want, "{:?}", pattern);
+            assert_eq!(searcher.rfind(""), want, "{:?}", pattern);
+            assert_eq!(searcher.rbmh(""), want, "{:?}", pattern);
+            assert_eq!(searcher.find_from("", 0), want, "{:?}", pattern);
+            assert_eq!(searcher.find_all("").count(), 0);
+            assert_eq!(searcher.split("").collect::<Vec<_>>(), vec![""]);
+            assert_eq!(searcher.non_matching_spans("").count(), 0);
+            assert_eq!(searcher.replace_all("", "x"), "");
+            
+            assert_eq!(linear_search(bytes, b""), want, "{:?}", pattern);
+            assert_eq!(kmp_search(bytes, b"", &border_table(bytes)), want, "{:?}", pattern);
+            assert_eq!(bmh_search(pattern, "", &bad_character_table(pattern)), want, "{:?}", pattern);
+            assert_eq!(BMHBytesPattern::new(bytes).bmh(b""), want, "{:?}", pattern);
+            assert_eq!(RabinKarpPattern::new(bytes).rabin_karp(b""), want, "{:?}", pattern);
+            assert_eq!(SundayPattern::new(pattern).find(b""), want, "{:?}", pattern);
+            assert_eq!(BoyerMoorePattern::new(pattern).bm(""), want, "{:?}", pattern);
+            assert_eq!(BoyerMoorePattern::new(pattern).ag(""), want, "{:?}", pattern);
+            assert_eq!(super::two_way(bytes, b""), want, "{:?}", pattern);
+            assert_eq!(super::z_search(bytes, b""), want, "{:?}", pattern);
+            assert_eq!(super::shift_or(bytes, b""), want, "{:?}", pattern);
+            assert_eq!(super::shift_and(bytes, b""), want, "{:?}", pattern);
+            assert_eq!(super::bndm(bytes, b""), want, "{:?}", pattern);
+            assert_eq!(super::kmp_search_iter(bytes, Vec::new()), want, "{:?}", pattern);
+            assert_eq!(super::find(bytes, b""), want, "{:?}", pattern);
+        }
+    }
+    
+    #[test]
+    fn short_border_tables() {
+        assert_eq!(border_table::<char>(&[]), Vec::<usize>::new());
+        assert_eq!(border_table(&['a']), vec![0]);
+        assert_eq!(border_table(&['a', 'a']), vec![0, 1]);
+        assert_eq!(border_table(&['a', 'b']), vec![0, 0]);
+    }
+    
+    #[test]
+    fn kmp_single_char() {
+        let text = TEXT.chars().collect::<Vec<_>>();
+        for &(want, c) in [(Some(4), 'd'), (Some(24), 'n'), (None, 'z')].iter() {
+            let pattern = [c];
+            let mut searcher = KMPPattern::new(&pattern[..]);
+            assert_eq!(searcher.kmp(&text[..]), want);
+        }
+    }
+    
+    #[test]
+    fn find_all() {
+        let text = TEXT.chars().collect::<Vec<_>>();
+        let pattern = "he".chars().collect::<Vec<_>>();
+        let mut searcher = KMPPattern::new(&pattern[..]);
+        assert_eq!(searcher.find_all(&text[..]).collect::<Vec<_>>(), vec![1, 22]);
+        
+        let pattern = "dead".chars().collect::<Vec<_>>();
+        let mut searcher = KMPPattern::new(&pattern[..]);
+        assert_eq!(searcher.find_all(&text[..]).collect::<Vec<_>>(), vec![16]);
+        
+        let pattern = "frank".chars().collect::<Vec<_>>();
+        let mut searcher = KMPPattern::new(&pattern[..]);
+        assert_eq!(searcher.find_all(&text[..]).next(), None);
+        
+        let mut searcher = KMPPattern::new(&[]);
+        assert_eq!(searcher.find_all(&text[..]).next(), None);
+    }
+   
+    #[test]
+    fn count() {
+        let text = TEXT.chars().collect::<Vec<_>>();
+        let pattern = "the".chars().collect::<Vec<_>>();
+        let mut searcher = KMPPattern::new(&pattern[..]);
+        assert_eq!(searcher.count(&text[..]), 2);
+        
+        let mut searcher = KMPPattern::new("a".as_bytes());
+        assert_eq!(searcher.count(b"aaaa"), 4);
+        assert_eq!(searcher.count(b""), 0);
+        
+        let mut searcher = KMPPattern::new(&[]);
+        assert_eq!(searcher.count(b"aaaa"), 0);
+    }
+    
+    #[test]
+    fn contains() {
+        let text = TEXT.chars().collect::<Vec<_>>();
+        for &(want, pattern) in CASES.iter() {
+            let chars = pattern.chars().collect::<Vec<_>>();
+            let mut searcher = KMPPattern::new(&chars[..]);
+            assert_eq!(searcher.contains(&text[..]), want.is_some());
+        }
+        
+        // The empty pattern is in everything, including empty text.
+        let mut searcher = KMPPattern::new(&[]);
+        assert!(searcher.contains(&text[..]));
+        assert!(searcher.contains(&[]));
+    }
+    
+    #[test]
+    fn find_word() {
+        let text = TEXT.chars().collect::<Vec<_>>();
+        let pattern = "the".chars().collect::<Vec<_>>();
+        let mut searcher = KMPPattern::new(&pattern[..]);
+        assert_eq!(searcher.find_word(&text[..]), Some(0));
+        // The `the` in `then` isn't a word by itself.
+        assert_eq!(searcher.find_word(&text[1..]), None);
+        
+        let text = "then, the_end the.".chars().collect::<Vec<_>>();
+        assert_eq!(searcher.find_word(&text[..]), Some(14));
+        
+        let pattern = "aa".chars().collect::<Vec<_>>();
+        let mut searcher = KMPPattern::new(&pattern[..]);
+        let text = "aaa aa".chars().collect::<Vec<_>>();
+        assert_eq!(searcher.find_word(&text[..]), Some(4));
+    }
+    
+    #[test]
+    fn find_match() {
+        let text = TEXT.chars().collect::<Vec<_>>();
+        let pattern = "very".chars().collect::<Vec<_>>();
+        let mut searcher = KMPPattern::new(&pattern[..]);
+        let m = searcher.find_match(&text[..]).unwrap();
+        assert_eq!(m, Match{ start: 11, end: 15 });
+        assert_eq!(&text[m.range()], &pattern[..]);
+        
+        let pattern = "frank".chars().collect::<Vec<_>>();
+        let mut searcher = KMPPattern::new(&pattern[..]);
+        assert_eq!(searcher.find_match(&text[..]), None);
+    }
+    
+    #[test]
+    fn find_split() {
+        let text = TEXT.chars().collect::<Vec<_>>();
+        let pattern = [' '];
+        let mut searcher = KMPPattern::new(&pattern[..]);
+        
+        // Split the text into words.
+        let mut rest = &text[..];
+        let mut words = Vec::new();
+        while let Some((start, tail)) = searcher.find_split(rest) {
+            assert_eq!(tail.as_ptr(), rest[start + pattern.len()..].as_ptr());
+            words.push(rest[..start].iter().collect::<String>());
+            rest = tail;
+        }
+        words.push(rest.iter().collect::<String>());
+        assert_eq!(words, vec!["the", "dog", "is", "very", "dead", "then"]);
+        
+        let pattern = ['t', 'h', 'e', 'n'];
+        let (start, tail) = KMPPattern::new(&pattern[..]).find_split(&text[..]).unwrap();
+        assert_eq!((start, tail.len()), (21, 0));
+        assert_eq!(KMPPattern::new(&['x'][..]).find_split(&text[..]), None);
+    }
+    
+    #[test]
+    fn find_slice() {
+        let text = TEXT.chars().collect::<Vec<_>>();
+        for &(want, pattern) in CASES.iter() {
+            let pattern = pattern.chars().collect::<Vec<_>>();
+            let found = KMPPattern::new(&pattern[..]).find_slice(&text[..]);
+            assert_eq!(found, want.map(|_| &pattern[..]));
+            // The slice is the text's, not the pattern's.
+            if let (Some(found), Some(start)) = (found, want) {
+                assert_eq!(found.as_ptr(), text[start..].as_ptr());
+            }
+        }
+    }
+    
+    #[test]
+    fn find_from() {
+        let text = TEXT.chars().collect::<Vec<_>>();
+        let pattern = "e".chars().collect::<Vec<_>>();
+        let mut searcher = KMPPattern::new(&pattern[..]);
+        
+        // Walk through the matches by resuming after each hit.
+        let mut hits = Vec::new();
+        let mut start = 0;
+        while let Some(i) = searcher.find_from(&text[..], start) {
+            hits.push(i);
+            start = i + 1;
+        }
+        assert_eq!(hits, vec![2, 12, 17, 23]);
+        
+        assert_eq!(searcher.find_from(&text[..], 13), Some(17));
+        assert_eq!(searcher.find_from(&text[..], 24), None);
+        assert_eq!(searcher.find_from(&text[..], 100), None);
+    }
+    
+    #[test]
+    fn find_skip_prefix() {
+        let text = TEXT.chars().collect::<Vec<_>>();
+        let pattern = "e".chars().collect::<Vec<_>>();
+        let mut searcher = KMPPattern::new(&pattern[..]);
+        assert_eq!(searcher.find_skip_prefix(&text[..], 0), Some(2));
+        assert_eq!(searcher.find_skip_prefix(&text[..], 3), Some(12));
+        assert_eq!(searcher.find_skip_prefix(&text[..], 24), None);
+        assert_eq!(searcher.find_skip_prefix(&text[..], 100), None);
+
+        // "dead" starts before the skipped prefix ends,
+        // so it isn't found even though it ends after it.
+        let pattern = "dead".chars().collect::<Vec<_>>();
+        let mut searcher = KMPPattern::new(&pattern[..]);
+        assert_eq!(searcher.find_skip_prefix(&text[..], 16), Some(16));
+        assert_eq!(searcher.find_skip_prefix(&text[..], 17), None);
+    }
+
+    #[test]
+    fn find_skip_prefix_never_looks_back() {
+        // Elements of the text which remember the lowest
+        // index of any of them which was compared.
+        struct Tracked<'a> {
+            c: u8,
+            index: usize,
+            lowest: &'a Cell<usize>,
+        }
+        impl<'a> PartialEq for Tracked<'a> {
+            fn eq(&self, other: &Tracked<'a>) -> bool {
+                let index = self.index.min(other.index);
+                self.lowest.set(self.lowest.get().min(index));
+                self.c == other.c
+            }
+        }
+
+        let lowest = Cell::new(usize::MAX);
+        let track = |bytes: &[u8], indexed: bool| bytes.iter().enumerate().map(|(i, &c)| {
+            Tracked{ c, index: if indexed { i } else { usize::MAX }, lowest: &lowest }
+        }).collect::<Vec<_>>();
+        let pattern = track(b"abab", false);
+        let text = track(b"abaabababab", true);
+        let mut searcher = KMPPattern::new(&pattern[..]);
+        assert_eq!(searcher.find_skip_prefix(&text[..], 4).map(|i| text[i].index), Some(5));
+        assert_eq!(lowest.get(), 4);
+    }
+
+    #[test]
+    fn longest_partial_suffix_match() {
+        let mut searcher = KMPPattern::new(b"abcd");
+        assert_eq!(searcher.longest_partial_suffix_match(b"xxabc"), 3);
+        assert_eq!(searcher.longest_partial_suffix_match(b"xxab"), 2);
+        assert_eq!(searcher.longest_partial_suffix_match(b"abcx"), 0);
+        assert_eq!(searcher.longest_partial_suffix_match(b""), 0);
+        // The whole pattern counts as a prefix of itself.
+        assert_eq!(searcher.longest_partial_suffix_match(b"xabcd"), 4);
+        assert_eq!(searcher.longest_partial_suffix_match(b"abcda"), 1);
+        
+        // The prefix can be a border of an earlier partial match.
+        let mut searcher = KMPPattern::new(b"abab");
+        assert_eq!(searcher.longest_partial_suffix_match(b"ababa"), 3);
+        assert_eq!(searcher.longest_partial_suffix_match(b"abaab"), 2);
+        assert_eq!(KMPPattern::new(b"").longest_partial_suffix_match(b"abc"), 0);
+    }
+    
+    #[test]
+    fn from() {
+        fn first(mut searcher: KMPPattern<u8>, text: &str) -> Option<usize> {
+            searcher.kmp(text.as_bytes())
+        }
+        fn first_bmh(mut searcher: BMHPattern, text: &str) -> Option<usize> {
+            searcher.bmh(text)
+        }
+        
+        let mut searcher: KMPPattern<u8> = "dead".as_bytes().into();
+        assert_eq!(searcher.kmp(TEXT.as_bytes()), Some(16));
+        let chars = "dog".chars().collect::<Vec<_>>();
+        let mut searcher: KMPPattern<char> = chars[..].into();
+        assert_eq!(searcher.kmp(&TEXT.chars().collect::<Vec<_>>()[..]), Some(4));
+        assert_eq!(first("then".as_bytes().into(), TEXT), Some(21));
+        
+        let mut searcher: BMHPattern = "the".into();
+        assert_eq!(searcher.bmh(TEXT), Some(0));
+        assert_eq!(first_bmh("very".into(), TEXT), Some(11));
+        assert_eq!(first_bmh(BMHPattern::from("frank"), TEXT), None);
+    }
+    
+    #[test]
+    fn matches_at_start() {
+        let text = TEXT.chars().collect::<Vec<_>>();
+        for &(want, pattern) in CASES.iter() {
+            let chars = pattern.chars().collect::<Vec<_>>();
+            let searcher = KMPPattern::new(&chars[..]);
+            assert_eq!(searcher.matches_at_start(&text[..]), want == Some(0));
+            assert!(searcher.borders.is_none());
+        }
+        // A pattern longer than the text can't be at its start.
+        let searcher = KMPPattern::new(b"the dog");
+        assert!(!searcher.matches_at_start(b"the"));
+        assert!(KMPPattern::new(b"").matches_at_start(b""));
+        assert!(KMPPattern::new(b"\x7fELF").matches_at_start(b"\x7fELF\x02\x01"));
+    }
+    
+    #[test]
+    fn find_in_range() {
+        let text = TEXT.chars().collect::<Vec<_>>();
+        let pattern = "e".chars().collect::<Vec<_>>();
+        let mut searcher = KMPPattern::new(&pattern[..]);
+        assert_eq!(searcher.find_in_range(&text[..], 0..text.len()), Some(2));
+        // Matches outside the range are ignored, but the offset is
+        // still into the whole text.
+        assert_eq!(searcher.find_in_range(&text[..], 3..20), Some(12));
+        assert_eq!(searcher.find_in_range(&text[..], 13..17), None);
+        assert_eq!(searcher.find_in_range(&text[..], 13..18), Some(17));
+        // The match has to fit inside the range.
+        let pattern = "dead".chars().collect::<Vec<_>>();
+        let mut searcher = KMPPattern::new(&pattern[..]);
+        assert_eq!(searcher.find_in_range(&text[..], 10..19), None);
+        assert_eq!(searcher.find_in_range(&text[..], 10..20), Some(16));
+        // Ranges past the end are clamped, and empty or inverted ones
+        // have no matches.
+        assert_eq!(searcher.find_in_range(&text[..], 10..1000), Some(16));
+        assert_eq!(searcher.find_in_range(&text[..], 1000..2000), None);
+        assert_eq!(searcher.find_in_range(&text[..], 16..16), None);
+        #[allow(clippy::reversed_empty_ranges)]
+        let inverted = 20..16;
+        assert_eq!(searcher.find_in_range(&text[..], inverted), None);
+    }
+    
+    #[test]
+    fn rfind() {
+        let text = TEXT.chars().collect::<Vec<_>>();
+        for &(want, pattern) in [
+            (Some(23), "e"),
+            (Some(22), "he"),
+            (Some(19), "d "),
+            (Some(0),  "the dog"),
+            (None,     "frank"),
+        ].iter() {
+            let chars = pattern.chars().collect::<Vec<_>>();
+            let mut searcher = KMPPattern::new(&chars[..]);
+            assert_eq!(searcher.rfind(&text[..]), want);
+        }
+        
+        let mut searcher = KMPPattern::new("aba".as_bytes());
+        assert_eq!(searcher.rfind(b"abababaab"), Some(4));
+    }
+    
+    #[test]
+    fn searchers_agree() {
+        for &(want, pattern) in CASES.iter() {
+            let mut searchers: Vec<Box<dyn Searcher<u8>>> = vec![
+                Box::new(LinearPattern::new(pattern.as_bytes())),
+                Box::new(KMPPattern::new(pattern.as_bytes())),
+                Box::new(BMHPattern::new(pattern)),
+                Box::new(BMHBytesPattern::new(pattern.as_bytes())),
+                Box::new(RabinKarpPattern::new(pattern.as_bytes())),
+                Box::new(SundayPattern::new(pattern)),
+            ];
+            for searcher in searchers.iter_mut() {
+                assert_eq!(searcher.find(TEXT.as_bytes()), want);
+            }
+        }
+    }
+    
+    #[test]
+    fn search_algorithms() {
+        for &(want, pattern) in CASES.iter() {
+            let mut searcher = BMHPattern::new(pattern);
+            assert_eq!(search(Algorithm::Linear, pattern, TEXT), searcher.linear(TEXT));
+            assert_eq!(search(Algorithm::Kmp, pattern, TEXT), searcher.kmp(TEXT));
+            assert_eq!(search(Algorithm::Bmh, pattern, TEXT), searcher.bmh(TEXT));
+            assert_eq!(search(Algorithm::Auto, pattern, TEXT), want);
+        }
+        for &pattern in ["", "d", "ery", "very"].iter() {
+            assert_eq!(search(Algorithm::Auto, pattern, TEXT), TEXT.find(pattern));
+        }
+    }
+    
+    #[test]
+    fn find() {
+        for &(want, pattern) in CASES.iter() {
+            assert_eq!(super::find(pattern.as_bytes(), TEXT.as_bytes()), want);
+            assert_eq!(super::find(pattern.as_bytes(), TEXT.as_bytes()), BMHPattern::new(pattern).bmh(TEXT));
+        }
+        assert_eq!(super::find(b"", b""), Some(0));
+        assert_eq!(super::find(b"\xff", b"\x00\xff"), Some(1));
+        assert_eq!(super::find(b"\xff\xfe\x00\x01", b"\x00\xff\xfe\x00\x01"), Some(1));
+    }
+    
+    #[test]
+    fn clone_keeps_tables() {
+        let mut searcher = KMPPattern::new("dead".as_bytes());
+        assert_eq!(searcher.kmp(TEXT.as_bytes()), Some(16));
+        let mut clone = searcher.clone();
+        assert_eq!(clone.borders, Some(vec![0, 0, 0, 1]));
+        assert_eq!(clone.kmp(TEXT.as_bytes()), Some(16));
+        
+        let mut searcher = BMHPattern::new("dead");
+        assert_eq!(searcher.bmh(TEXT), Some(16));
+        let mut clone = searcher.clone();
+        assert!(clone.u8_bmh.bad_char_table.is_some());
+        assert_eq!(clone.bmh(TEXT), Some(16));
+        assert!(format!("{:?}", clone).starts_with("BMHPattern"));
+    }
+    
+    #[test]
+    fn borders() {
+        let mut searcher = KMPPattern::new("ababaca".as_bytes());
+        assert_eq!(searcher.borders(), &[0, 0, 1, 2, 3, 0, 1]);
+        assert_eq!(searcher.borders(), &border_table("ababaca".as_bytes())[..]);
+        assert_eq!(KMPPattern::new("aaaa".as_bytes()).borders(), &[0, 1, 2, 3]);
+        assert_eq!(KMPPattern::new("".as_bytes()).borders(), &[] as &[usize]);
+    }
+    
+    #[test]
+    fn with_borders() {
+        let text = TEXT.chars().collect::<Vec<_>>();
+        for &(want, pattern) in CASES.iter() {
+            let chars = pattern.chars().collect::<Vec<_>>();
+            let mut normal = KMPPattern::new(&chars[..]);
+            let mut prebuilt = KMPPattern::with_borders(&chars[..], border_table(&chars[..]));
+            assert_eq!(prebuilt.borders(), normal.borders());
+            assert_eq!(prebuilt.kmp(&text[..]), want);
+            assert_eq!(prebuilt.find_all(&text[..]).collect::<Vec<_>>(),
+                       normal.find_all(&text[..]).collect::<Vec<_>>());
+        }
+        let mut empty = KMPPattern::with_borders(b"", vec![]);
+        assert_eq!(empty.kmp(TEXT.as_bytes()), Some(0));
+    }
+    
+    #[test]
+    #[should_panic]
+    #[cfg(debug_assertions)]
+    fn with_borders_wrong_length() {
+        KMPPattern::with_borders(b"ab", vec![0]);
+    }
+    
+    #[test]
+    fn for_each_match() {
+        let text = TEXT.chars().collect::<Vec<_>>();
+        let pattern = "e".chars().collect::<Vec<_>>();
+        let mut searcher = KMPPattern::new(&pattern[..]);
+        let mut hits = Vec::new();
+        searcher.for_each_match(&text[..], |start| hits.push(start));
+        assert_eq!(hits, searcher.find_all(&text[..]).collect::<Vec<_>>());
+        
+        // Breaking stops the search, and takes its value with it.
+        let mut hits = Vec::new();
+        let flow = searcher.try_for_each_match(&text[..], |start| {
+            hits.push(start);
+            if hits.len() == 2 { ControlFlow::Break(start) } else { ControlFlow::Continue(()) }
+        });
+        assert_eq!(flow, ControlFlow::Break(12));
+        assert_eq!(hits, vec![2, 12]);
+        assert_eq!(searcher.try_for_each_match(&text[..], |_| ControlFlow::<()>::Continue(())),
+                   ControlFlow::Continue(()));
+    }
+    
+    #[test]
+    fn clear_cache() {
+        let mut searcher = KMPPattern::new("dead".as_bytes());
+        let before = searcher.kmp(TEXT.as_bytes());
+        assert!(searcher.borders.is_some());
+        searcher.clear_cache();
+        assert!(searcher.borders.is_none());
+        assert_eq!(searcher.kmp(TEXT.as_bytes()), before);
+        assert!(searcher.borders.is_some());
+        
+        let mut searcher = BMHPattern::new("dead");
+        let before = (searcher.bmh(TEXT), searcher.kmp(TEXT), searcher.bmh_ignore_ascii_case(TEXT));
+        searcher.clear_cache();
+        assert!(searcher.u8_bmh.bad_char_table.is_none());
+        assert!(searcher.u8_kmp.borders.is_none());
+        assert!(searcher.ascii_case_bad_char_table.is_none());
+        assert_eq!((searcher.bmh(TEXT), searcher.kmp(TEXT), searcher.bmh_ignore_ascii_case(TEXT)), before);
+    }
+    
+    #[test]
+    fn preprocess() {
+        // The tables are built once, and searching reuses them.
+        let mut searcher = KMPPattern::new("dead".as_bytes());
+        assert!(searcher.borders.is_none());
+        searcher.preprocess();
+        let borders = searcher.borders.as_ref().unwrap().as_ptr();
+        assert_eq!(searcher.kmp(TEXT.as_bytes()), Some(16));
+        assert_eq!(searcher.borders.as_ref().unwrap().as_ptr(), borders);
+        
+        let mut searcher = BMHPattern::new("dead");
+        assert!(searcher.u8_bmh.bad_char_table.is_none());
+        searcher.preprocess();
+        let bad_char_table = searcher.u8_bmh.bad_char_table.as_ref().unwrap().as_ptr();
+        assert_eq!(searcher.bmh(TEXT), Some(16));
+        assert_eq!(searcher.u8_bmh.bad_char_table.as_ref().unwrap().as_ptr(), bad_char_table);
+        
+        // An empty pattern has nothing to build.
+        let mut searcher = KMPPattern::new(&b""[..]);
+        searcher.preprocess();
+        assert!(searcher.borders.is_none());
+    }
+    
+    #[test]
+    fn kmp_search_iter() {
+        let text = TEXT.chars().collect::<Vec<_>>();
+        for &(want, pattern) in CASES.iter() {
+            let pattern = pattern.chars().collect::<Vec<_>>();
+            assert_eq!(super::kmp_search_iter(&pattern, TEXT.chars()), want);
+            assert_eq!(super::kmp_search_iter(&pattern, TEXT.chars()),
+                       kmp_search(&pattern, &text, &border_table(&pattern)));
+        }
+        
+        // Indexes count elements, not bytes.
+        assert_eq!(super::kmp_search_iter(&['b', 'c'], "çabc".chars()), Some(2));
+        let mut random = Random(0x9fb21c651e98df25);
+        for _ in 0..1000 {
+            let pattern = random.bytes(6, 2);
+            let text = random.bytes(20, 2);
+            assert_eq!(super::kmp_search_iter(&pattern, text.iter().cloned()), linear_search(&pattern, &text));
+        }
+    }
+    
+    #[test]
+    fn tokens() {
+        // Tokens which can only be compared, not cloned, copied,
+        // hashed or printed, as from a lexer.
+        #[derive(PartialEq)]
+        enum Tok {
+            Ident(&'static str),
+            Number(u64),
+            Equals,
+            Semicolon,
+        }
+        fn lex() -> Vec<Tok> {
+            vec![Tok::Ident("x"), Tok::Equals, Tok::Number(1), Tok::Semicolon,
+                 Tok::Ident("y"), Tok::Equals, Tok::Number(2), Tok::Semicolon]
+        }
+
+        let text = lex();
+        let pattern = [Tok::Equals, Tok::Number(2)];
+        let borders = border_table(&pattern);
+
+        let mut searcher = KMPPattern::new(&pattern[..]);
+        assert_eq!(searcher.kmp(&text), Some(5));
+        assert_eq!(searcher.rfind(&text), Some(5));
+        assert_eq!(searcher.find_all(&text).collect::<Vec<_>>(), vec![5]);
+        assert_eq!(linear_search(&pattern, &text), Some(5));
+        assert_eq!(kmp_search(&pattern, &text, &borders), Some(5));
+        // The tokens can be moved straight out of the lexer.
+        assert_eq!(super::kmp_search_iter(&pattern, lex()), Some(5));
+
+        let statement_end = [Tok::Semicolon];
+        let mut automaton = KmpAutomaton::new(&statement_end[..]);
+        assert!(automaton.feed(&text) == StreamResult::Found(3));
+
+        let preprocessed = PreprocessedKmp{ pattern: vec![Tok::Ident("y"), Tok::Equals], borders: vec![0, 0] };
+        assert_eq!(preprocessed.searcher().kmp(&text), Some(4));
+
+        let mut incremental = IncrementalKmp::new();
+        incremental.push(Tok::Number(1));
+        incremental.push(Tok::Semicolon);
+        assert_eq!(incremental.find(&text), Some(2));
+    }
+
+    #[test]
+    fn kmp_pattern_longer_than_text() {
+        let pattern = b"abcabcabca";
+        assert_eq!(kmp_search(pattern, b"abc", &border_table(pattern)), None);
+        let mut searcher = KMPPattern::new(&pattern[..]);
+        assert_eq!(searcher.kmp(b"abc"), None);
+        assert!(searcher.borders.is_none());
+        assert_eq!(searcher.kmp(b""), None);
+        assert_eq!(searcher.kmp(pattern), Some(0));
+    }
+    
+    #[test]
+    fn kmp_after_partial_match() {
+        // Each of these backs off from a partial match of the pattern.
+        let cases = [
+            (None,    "babb", "abaabb"),
+            (Some(5), "aba",  "bbabbabab"),
+            (Some(1), "bba",  "bbbaabb"),
+            (Some(3), "abab", "abaabab"),
+        ];
+        for &(want, pattern, text) in cases.iter() {
+            let mut searcher = KMPPattern::new(pattern.as_bytes());
+            assert_eq!(searcher.kmp(text.as_bytes()), want);
+        }
+    }
+    
+    #[test]
+    fn bmh_search_traced() {
+        for &(want, pattern) in CASES.iter() {
+            let (found, windows) = super::bmh_search_traced(pattern, TEXT, &bad_character_table(pattern));
+            assert_eq!(found, want);
+            // Each shift moves forwards.
+            assert!(windows.windows(2).all(|w| w[0] < w[1]), "{:?}: {:?}", pattern, windows);
+            if let Some(start) = want {
+                assert!(windows.last().is_none_or(|&t| t == start));
+            }
+        }
+        
+        // "cat" isn't in the text, so the scan skips to the window
+        // ending at the "t" of "then", which is then shifted past.
+        let (found, windows) = super::bmh_search_traced("cat", TEXT, &bad_character_table("cat"));
+        assert_eq!(found, None);
+        assert_eq!(windows, vec![19, 21]);
+        assert!(windows.iter().zip(windows.iter().skip(1)).any(|(a, b)| b - a > 1));
+    }
+    
+    #[test]
+    fn horspool() {
+        for &(want, pattern) in CASES.iter() {
+            let bad_char_table = bad_character_table_horspool(pattern.as_bytes());
+            assert_eq!(horspool_search(pattern.as_bytes(), TEXT.as_bytes(), &bad_char_table), want);
+        }
+        
+        // The tables only differ for the last character of the pattern.
+        let pattern = b"xab";
+        let (horspool, bmh) = (bad_character_table_horspool(pattern), bad_character_table_bytes(pattern));
+        assert_eq!((horspool[b'b' as usize], bmh[b'b' as usize]), (3, 0));
+        assert_eq!((horspool[b'x' as usize], horspool[b'a' as usize]), (bmh[b'x' as usize], bmh[b'a' as usize]));
+        assert!(horspool.iter().all(|&shift| shift > 0));
+        
+        // In "yabxab", both mismatch on the "y" of the first window.
+        // It's two from the end, so BMH can only shift by one past it,
+        // then by two for the "x", while Horspool shifts by the "b" at
+        // the end of the window, straight to the match.
+        let text = b"yabxab";
+        let mut bmh_compared = Vec::new();
+        let bmh_found = bmh_search_by(pattern, text, &bmh, |a, b| { bmh_compared.push(a); a == b });
+        let mut horspool_compared = Vec::new();
+        let horspool_found = horspool_search_by(pattern, text, &horspool, |a, b| { horspool_compared.push(a); a == b });
+        assert_eq!((bmh_found, horspool_found), (Some(3), Some(3)));
+        assert_eq!(bmh_compared, b"bay".iter().chain(b"x").chain(b"bax").cloned().collect::<Vec<_>>());
+        assert_eq!(horspool_compared, b"baybax");
+        
+        let mut random = Random(0xa0761d6478bd642f);
+        for _ in 0..10000 {
+            let pattern = random.bytes(6, 3);
+            let text = random.bytes(20, 3);
+            assert_eq!(horspool_search(&pattern, &text, &bad_character_table_horspool(&pattern)),
+                       linear_search(&pattern, &text), "{:?} in {:?}", pattern, text);
+        }
+    }
     
     #[test]
-    fn linear() {
-        let text = TEXT.chars().collect::<Vec<_>>();
-        for &(want, pattern) in (&CASES).iter() {
-            let chars = pattern.chars().collect::<Vec<_>>();
-            let searcher = KMPPattern::new(&chars[..]);
-            assert_eq!(searcher.linear(&text[..]), want);
+    fn bmh_never_shifts_by_zero() {
+        // The pattern's last byte has a shift of zero in the table,
+        // so a mismatch on it has to shift by at least one.
+        let table = bad_character_table("aba");
+        assert_eq!(table[b'a' as usize], 0);
+        assert_eq!(bad_character_shift(&table, b'a', 0), 1);
+        assert_eq!(bad_character_shift(&table, b'a', 1), 1);
+        
+        // Each window ends in "a", then mismatches on another "a".
+        let text = "aaaaaaaaaba";
+        assert_eq!(bmh_search("aba", text, &table), Some(8));
+        assert_eq!(bmh_search_by(b"aba", text.as_bytes(), &table, |a, b| a == b), Some(8));
+        assert_eq!(BMHPattern::new("aba").raita(text), Some(8));
+        assert_eq!(BMHPattern::new("ABA").bmh_ignore_ascii_case(text), Some(8));
+        assert_eq!(BMHPattern::new("aba").bmh("aaaa"), None);
+    }
+    
+    #[test]
+    fn bmh_skips_windows() {
+        // None of the text is in the pattern, so plain BMH compares
+        // one byte in every window it shifts to.
+        let pattern = b"needle";
+        let mut text = vec![b'x'; 1 << 20];
+        text.extend_from_slice(pattern);
+        let bad_char_table = bad_character_table_bytes(pattern);
+        
+        let mut plain_comparisons = 0;
+        let plain = bmh_search_by(pattern, &text, &bad_char_table, |a, b| {
+            plain_comparisons += 1;
+            a == b
+        });
+        let mut skip_comparisons = 0;
+        let skip = bmh_skip_search_by(pattern, &text, &bad_char_table, |a, b| {
+            skip_comparisons += 1;
+            a == b
+        });
+        
+        assert_eq!(plain, Some(1 << 20));
+        assert_eq!(skip, plain);
+        assert!(plain_comparisons > (1 << 20) / pattern.len());
+        // Only the windows ending in one of the "e"s get compared.
+        assert!(skip_comparisons <= 2 * pattern.len(), "{} comparisons", skip_comparisons);
+    }
+    
+    #[test]
+    fn any_input_agrees_with_windows() {
+        // A reference which is too simple to be wrong.
+        fn naive(pattern: &[u8], text: &[u8]) -> Option<usize> {
+            if pattern.is_empty() {
+                return Some(0);
+            }
+            text.windows(pattern.len()).position(|window| window == pattern)
+        }
+        
+        // Any bytes at all, including empty patterns and texts, patterns
+        // longer than the text, and the ends of the byte range. Random
+        // patterns rarely match, so some are taken from the text, and
+        // some use only a few bytes from the ends of the range.
+        let mut random = Random(0xbf58476d1ce4e5b9);
+        for i in 0..20000 {
+            let text = random.any_bytes(40);
+            let pattern = match i % 3 {
+                0 => random.any_bytes(10),
+                1 => {
+                    let start = random.below(text.len() as u64 + 1) as usize;
+                    let end = start + random.below((text.len() - start) as u64 + 1) as usize;
+                    text[start..end].to_vec()
+                },
+                _ => random.bytes(6, 3).iter().map(|&b| [0x00, 0x80, 0xff][(b - b'a') as usize]).collect(),
+            };
+            
+            let want = naive(&pattern, &text);
+            assert_eq!(linear_search(&pattern, &text), want, "{:?} in {:?}", pattern, text);
+            assert_eq!(kmp_search(&pattern, &text, &border_table(&pattern)), want, "{:?} in {:?}", pattern, text);
+            assert_eq!(BMHBytesPattern::new(&pattern).bmh(&text), want, "{:?} in {:?}", pattern, text);
+            if let Ok(pattern) = str::from_utf8(&pattern) {
+                assert_eq!(BMHPattern::new(pattern).bmh(&text), want, "{:?} in {:?}", pattern, text);
+            }
         }
     }
-
+    
+    // A reference for the properties, which is too simple to be wrong.
+    fn naive_search(pattern: &[u8], text: &[u8]) -> Option<usize> {
+        if pattern.is_empty() {
+            return Some(0);
+        }
+        text.windows(pattern.len()).position(|window| window == pattern)
+    }
+    
+    // Any bytes, weighted towards a few from each end of the byte range,
+    // so that patterns match often, and bytes from 0x80 to 0xFF appear
+    // in every input which isn't empty.
+    fn any_bytes(max_len: usize) -> impl Strategy<Value = Vec<u8>> {
+        let byte = prop_oneof![any::<u8>(), prop::sample::select(vec![0x00u8, 0x61, 0x80, 0xff])];
+        prop::collection::vec(byte, 0..=max_len)
+    }
+    
+    proptest! {
+        #[test]
+        fn agrees_with_naive(pattern in any_bytes(6), text in any_bytes(40)) {
+            let want = naive_search(&pattern, &text);
+            prop_assert_eq!(linear_search(&pattern, &text), want);
+            prop_assert_eq!(kmp_search(&pattern, &text, &border_table(&pattern)), want);
+            prop_assert_eq!(BMHBytesPattern::new(&pattern).bmh(&text), want);
+        }
+        
+        #[test]
+        fn agrees_with_naive_in_text(text in any_bytes(40), start in 0..=40usize, len in 0..=8usize) {
+            // A slice of the text, so that it always matches.
+            let start = cmp::min(start, text.len());
+            let pattern = text[start..cmp::min(start + len, text.len())].to_vec();
+            let want = naive_search(&pattern, &text);
+            prop_assert!(want.is_some());
+            prop_assert_eq!(linear_search(&pattern, &text), want);
+            prop_assert_eq!(kmp_search(&pattern, &text, &border_table(&pattern)), want);
+            prop_assert_eq!(BMHBytesPattern::new(&pattern).bmh(&text), want);
+        }
+        
+        #[test]
+        fn str_agrees_with_naive(pattern in "[a\\x{80}\\x{ff}\\x{10ffff}]{0,4}", text in "[ab\\x{80}\\x{ff}\\x{10ffff}]{0,20}") {
+            // Non-ASCII characters encode as bytes from 0x80 up.
+            let want = naive_search(pattern.as_bytes(), text.as_bytes());
+            prop_assert_eq!(BMHPattern::new(&pattern).bmh(&text), want);
+            prop_assert_eq!(text.find(&pattern[..]), want);
+        }
+    }
+    
     #[test]
-    fn kmp() {
-        let text = TEXT.chars().collect::<Vec<_>>();
-        for &(want, pattern) in CASES.iter() {
-            let chars = pattern.chars().collect::<Vec<_>>();
-            let mut searcher = KMPPattern::new(&chars[..]);
-            assert_eq!(searcher.kmp(&text[..]), want);
+    fn bmh_skip_agrees_with_linear() {
+        let mut random = Random(0x9e3779b97f4a7c15);
+        for _ in 0..10000 {
+            let pattern = random.bytes(6, 3);
+            let text = random.bytes(30, 3);
+            let bad_char_table = bad_character_table_bytes(&pattern);
+            assert_eq!(bmh_skip_search_by(&pattern, &text, &bad_char_table, |a, b| a == b),
+                       linear_search(&pattern, &text), "{:?} in {:?}", pattern, text);
+        }
+    }
+    
+    // Counts every comparison made between two of them.
+    #[derive(Debug)]
+    struct Counted(u8);
+    
+    thread_local! {
+        static COMPARISONS: Cell<usize> = const { Cell::new(0) };
+    }
+    
+    impl PartialEq for Counted {
+        fn eq(&self, other: &Counted) -> bool {
+            COMPARISONS.with(|c| c.set(c.get() + 1));
+            self.0 == other.0
         }
     }
+    
+    #[test]
+    fn find_all_is_linear() {
+        // Every position of a run of "a"s matches a periodic pattern,
+        // so anything which rescanned a match would be quadratic.
+        let n = 100_000;
+        let text = (0..n).map(|_| Counted(b'a')).collect::<Vec<_>>();
+        let pattern = (0..4).map(|_| Counted(b'a')).collect::<Vec<_>>();
+        let mut searcher = KMPPattern::new(&pattern[..]);
+        searcher.borders();
+        
+        COMPARISONS.with(|c| c.set(0));
+        assert_eq!(searcher.find_overlapping(&text[..]).count(), n - 3);
+        let overlapping = COMPARISONS.with(|c| c.get());
+        COMPARISONS.with(|c| c.set(0));
+        assert_eq!(searcher.find_all(&text[..]).count(), n / 4);
+        let all = COMPARISONS.with(|c| c.get());
+        
+        assert!(overlapping <= 2 * n, "{} comparisons", overlapping);
+        assert!(all <= 2 * n, "{} comparisons", all);
+    }
+    
+    #[test]
+    fn find_overlapping() {
+        let mut searcher = KMPPattern::new("aa".as_bytes());
+        assert_eq!(searcher.find_overlapping(b"aaaa").collect::<Vec<_>>(), vec![0, 1, 2]);
+        assert_eq!(searcher.find_all(b"aaaa").collect::<Vec<_>>(), vec![0, 2]);
+        
+        let mut searcher = KMPPattern::new("aba".as_bytes());
+        assert_eq!(searcher.find_overlapping(b"abababa").collect::<Vec<_>>(), vec![0, 2, 4]);
+        assert_eq!(searcher.find_all(b"abababa").collect::<Vec<_>>(), vec![0, 4]);
+        
+        // Without any self-overlap the two agree.
+        let text = TEXT.chars().collect::<Vec<_>>();
+        let pattern = "he".chars().collect::<Vec<_>>();
+        let mut searcher = KMPPattern::new(&pattern[..]);
+        assert_eq!(searcher.find_overlapping(&text[..]).collect::<Vec<_>>(), vec![1, 22]);
+    }
    
     #[cfg(test)]
     mod bmh_pattern {
-        use super::BMHPattern;
-        use super::CASES;
+        use std::string::{String, ToString};
+        use std::vec::Vec;
+        use super::super::{bad_character_table, bmh_search_by, raita_search_by, BMHBytesPattern, SearchError};
+        use super::{BMHPattern, KMPPattern};
+        use core::ops::ControlFlow;
+        use std::borrow::Cow;
+        use std::sync::Arc;
+        use std::thread;
+        use super::{CASES, Random};
         use super::TEXT;
         
         #[test]
         fn linear() {
-            for &(want, pattern) in (&CASES).iter() {
+            for &(want, pattern) in CASES.iter() {
                 let searcher = BMHPattern::new(pattern);
                 assert_eq!(searcher.linear(TEXT), want);
             }
@@ -282,5 +2760,652 @@ mod correct_return {
                 assert_eq!(searcher.bmh(TEXT), want);
             }
         }
+
+        #[test]
+        fn find_all() {
+            let mut searcher = BMHPattern::new("e");
+            assert_eq!(searcher.find_all(TEXT).collect::<Vec<_>>(), vec![2, 12, 17, 23]);
+            
+            // Matches don't overlap.
+            let mut searcher = BMHPattern::new("aa");
+            assert_eq!(searcher.find_all("aaaaa").collect::<Vec<_>>(), vec![0, 2]);
+            
+            let mut searcher = BMHPattern::new("");
+            assert_eq!(searcher.find_all(TEXT).next(), None);
+        }
+        
+        #[test]
+        fn count() {
+            let mut searcher = BMHPattern::new("the");
+            assert_eq!(searcher.count(TEXT), 2);
+            
+            let mut searcher = BMHPattern::new("a");
+            assert_eq!(searcher.count("aaaa"), 4);
+            assert_eq!(searcher.count(""), 0);
+            
+            let mut searcher = BMHPattern::new("");
+            assert_eq!(searcher.count(TEXT), 0);
+        }
+        
+        #[test]
+        fn contains() {
+            for &(want, pattern) in CASES.iter() {
+                let mut searcher = BMHPattern::new(pattern);
+                assert_eq!(searcher.contains(TEXT), want.is_some());
+            }
+            
+            // The empty pattern is in everything, as with std.
+            let mut searcher = BMHPattern::new("");
+            assert_eq!(searcher.contains(TEXT), TEXT.contains(""));
+            assert!(searcher.contains(""));
+        }
+        
+        #[test]
+        fn find_word() {
+            let mut searcher = BMHPattern::new("the");
+            assert_eq!(searcher.find_word(TEXT), Some(0));
+            assert_eq!(searcher.find_word(&TEXT[1..]), None);
+            assert_eq!(searcher.find_word("then, the_end (the)"), Some(15));
+            assert_eq!(searcher.find_word("bathe other"), None);
+            
+            let mut searcher = BMHPattern::new("dead");
+            assert_eq!(searcher.find_word(TEXT), Some(16));
+        }
+        
+        #[test]
+        fn find_match() {
+            let mut searcher = BMHPattern::new("dog");
+            let m = searcher.find_match(TEXT).unwrap();
+            assert_eq!(&TEXT[m.range()], "dog");
+            
+            // The end is in bytes, so multibyte text slices correctly.
+            let text = "le caf\u{e9} cr\u{e8}me";
+            let mut searcher = BMHPattern::new("caf\u{e9}");
+            let m = searcher.find_match(text).unwrap();
+            assert_eq!((m.start, m.end), (3, 8));
+            assert_eq!(&text[m.range()], "caf\u{e9}");
+        }
+        
+        #[test]
+        fn find_from() {
+            let mut searcher = BMHPattern::new("dead");
+            assert_eq!(searcher.find_from(TEXT, 0), Some(16));
+            assert_eq!(searcher.find_from(TEXT, 16), Some(16));
+            assert_eq!(searcher.find_from(TEXT, 17), None);
+            
+            // Starting inside a multibyte char skips to the next one.
+            let mut searcher = BMHPattern::new("\u{e9}");
+            let text = "caf\u{e9} caf\u{e9}";
+            assert_eq!(searcher.find_from(text, 0), Some(3));
+            assert_eq!(searcher.find_from(text, 4), Some(9));
+            assert_eq!(searcher.find_from(text, 12), None);
+        }
+        
+        #[test]
+        fn rfind() {
+            let mut searcher = BMHPattern::new("e");
+            assert_eq!(searcher.rfind(TEXT), Some(23));
+            let mut searcher = BMHPattern::new("/");
+            assert_eq!(searcher.rfind("/usr/local/bin"), Some(10));
+            let mut searcher = BMHPattern::new("frank");
+            assert_eq!(searcher.rfind(TEXT), None);
+        }
+        
+        #[test]
+        fn bmh_char_index() {
+            for &(want, pattern) in CASES.iter() {
+                let mut searcher = BMHPattern::new(pattern);
+                assert_eq!(searcher.bmh_char_index(TEXT), want);
+            }
+            
+            let text = "caf\u{e9} menu";
+            let mut searcher = BMHPattern::new("menu");
+            assert_eq!(searcher.bmh(text), Some(6));
+            assert_eq!(searcher.bmh_char_index(text), Some(5));
+            let chars = text.chars().collect::<Vec<_>>();
+            assert_eq!(chars[5..].iter().collect::<String>(), "menu");
+        }
+        
+        #[test]
+        fn bmh_ignore_ascii_case() {
+            for &(want, pattern) in CASES.iter() {
+                let upper = pattern.to_ascii_uppercase();
+                let mut searcher = BMHPattern::new(&upper);
+                assert_eq!(searcher.bmh_ignore_ascii_case(TEXT), want);
+            }
+            
+            let mut searcher = BMHPattern::new("THE");
+            assert_eq!(searcher.bmh_ignore_ascii_case(TEXT), Some(0));
+            let mut searcher = BMHPattern::new("DeAd");
+            assert_eq!(searcher.bmh_ignore_ascii_case(TEXT), Some(16));
+            assert_eq!(searcher.bmh(TEXT), None);
+            let mut searcher = BMHPattern::new("Dogs");
+            assert_eq!(searcher.bmh_ignore_ascii_case(TEXT), None);
+            
+            // Only ASCII letters are folded.
+            let mut searcher = BMHPattern::new("CAF\u{c9}");
+            assert_eq!(searcher.bmh_ignore_ascii_case("caf\u{c9}"), Some(0));
+            assert_eq!(searcher.bmh_ignore_ascii_case("caf\u{e9}"), None);
+        }
+        
+        #[test]
+        fn bmh_bytes() {
+            for &(want, pattern) in CASES.iter() {
+                let mut searcher = BMHBytesPattern::new(pattern.as_bytes());
+                assert_eq!(searcher.bmh(TEXT.as_bytes()), want);
+            }
+            
+            // None of these are valid UTF-8.
+            let text = [0x00, 0xff, 0xc3, 0xff, 0xfe, 0x80];
+            let mut searcher = BMHBytesPattern::new(&[0xff, 0xfe]);
+            assert_eq!(searcher.bmh(&text), Some(3));
+            let mut searcher = BMHBytesPattern::new(&[0xfe, 0x80]);
+            assert_eq!(searcher.bmh(&text), Some(4));
+            let mut searcher = BMHBytesPattern::new(&[0xfe, 0xff]);
+            assert_eq!(searcher.bmh(&text), None);
+        }
+        
+        #[test]
+        fn replace_all() {
+            let mut searcher = BMHPattern::new("e");
+            assert_eq!(searcher.replace_all(TEXT, "E"), "thE dog is vEry dEad thEn");
+            let mut searcher = BMHPattern::new("dead");
+            assert_eq!(searcher.replace_all(TEXT, ""), "the dog is very  then");
+            let mut searcher = BMHPattern::new("frank");
+            assert_eq!(searcher.replace_all(TEXT, "bob"), TEXT);
+            
+            // Matches don't overlap, and replacements aren't searched.
+            let mut searcher = BMHPattern::new("aa");
+            assert_eq!(searcher.replace_all("aaaa", "b"), "bb");
+            assert_eq!(searcher.replace_all("aaaaa", "b"), "bba");
+            assert_eq!(searcher.replace_all("aaaa", "a"), "aa");
+            
+            let mut searcher = BMHPattern::new("");
+            assert_eq!(searcher.replace_all(TEXT, "x"), TEXT);
+        }
+        
+        #[test]
+        fn replace_all_overlapping() {
+            let mut searcher = BMHPattern::new("aba");
+            assert_eq!(searcher.replace_all("ababa", "X"), "Xba");
+            assert_eq!(searcher.replace_all_overlapping("ababa", "X"), "XX");
+            assert_eq!(searcher.replace_all_overlapping("abababa", "<$>"), "<$><$><$>");
+            // Occurrences which don't overlap behave like `replace_all`.
+            assert_eq!(searcher.replace_all_overlapping("aba-aba", "X"), "X-X");
+            
+            for &pattern in ["e", "dead", "frank", ""].iter() {
+                let mut searcher = BMHPattern::new(pattern);
+                assert_eq!(searcher.replace_all_overlapping(TEXT, "x"), searcher.replace_all(TEXT, "x"));
+            }
+        }
+        
+        #[test]
+        fn replace_first() {
+            let mut searcher = BMHPattern::new("e");
+            assert_eq!(searcher.replace_first(TEXT, "E"), "thE dog is very dead then");
+            let mut searcher = BMHPattern::new("then");
+            assert_eq!(searcher.replace_first(TEXT, "now"), "the dog is very dead now");
+            let mut searcher = BMHPattern::new("frank");
+            assert_eq!(searcher.replace_first(TEXT, "bob"), TEXT);
+            let mut searcher = BMHPattern::new("");
+            assert_eq!(searcher.replace_first(TEXT, "x"), TEXT);
+        }
+        
+        #[test]
+        fn split() {
+            let mut searcher = BMHPattern::new(",");
+            assert_eq!(searcher.split("a,b,,c").collect::<Vec<_>>(), vec!["a", "b", "", "c"]);
+            assert_eq!(searcher.split(",a,").collect::<Vec<_>>(), vec!["", "a", ""]);
+            assert_eq!(searcher.split("").collect::<Vec<_>>(), vec![""]);
+            assert_eq!(searcher.split(TEXT).collect::<Vec<_>>(), vec![TEXT]);
+            
+            let mut searcher = BMHPattern::new(" d");
+            assert_eq!(searcher.split(TEXT).collect::<Vec<_>>(), vec!["the", "og is very", "ead then"]);
+            
+            let mut searcher = BMHPattern::new("");
+            assert_eq!(searcher.split(TEXT).collect::<Vec<_>>(), vec![TEXT]);
+        }
+        
+        #[test]
+        fn matches_with_context() {
+            let mut searcher = BMHPattern::new("e");
+            let matches = searcher.matches_with_context(TEXT, 2, 3).collect::<Vec<_>>();
+            // The first match is too close to the start for two chars
+            // before it, and the last is too close to the end.
+            assert_eq!(matches, vec![(2, "the do"), (12, " very "), (17, " dead "), (23, "then")]);
+
+            let mut searcher = BMHPattern::new("dead");
+            assert_eq!(searcher.matches_with_context(TEXT, 0, 0).collect::<Vec<_>>(), vec![(16, "dead")]);
+            assert_eq!(searcher.matches_with_context(TEXT, 100, 100).collect::<Vec<_>>(), vec![(16, TEXT)]);
+
+            // The context is counted in chars, not bytes.
+            let text = "caf\u{e9} \u{1F600}x\u{1F600} na\u{ef}ve";
+            let mut searcher = BMHPattern::new("x");
+            assert_eq!(searcher.matches_with_context(text, 2, 1).collect::<Vec<_>>(),
+                       vec![(10, " \u{1F600}x\u{1F600}")]);
+            let mut searcher = BMHPattern::new("caf");
+            assert_eq!(searcher.matches_with_context(text, 1, 1).collect::<Vec<_>>(), vec![(0, "caf\u{e9}")]);
+            let mut searcher = BMHPattern::new("ve");
+            assert_eq!(searcher.matches_with_context(text, 2, 1).collect::<Vec<_>>(), vec![(20, "a\u{ef}ve")]);
+
+            let mut searcher = BMHPattern::new("");
+            assert_eq!(searcher.matches_with_context(TEXT, 1, 1).count(), 0);
+        }
+
+        #[test]
+        fn non_matching_spans() {
+            let mut searcher = BMHPattern::new(",");
+            assert_eq!(searcher.non_matching_spans("a,b,,c").collect::<Vec<_>>(), vec![(0, 1), (2, 3), (5, 6)]);
+            assert_eq!(searcher.non_matching_spans(",,").count(), 0);
+            assert_eq!(searcher.non_matching_spans("").count(), 0);
+            assert_eq!(BMHPattern::new("").non_matching_spans(TEXT).collect::<Vec<_>>(), vec![(0, TEXT.len())]);
+            
+            // The spans and the matches tile the text.
+            for &pattern in ["e", "d", " d", "the", "then", "dead", "cat"].iter() {
+                let mut searcher = BMHPattern::new(pattern);
+                let mut tiles = searcher.non_matching_spans(TEXT).collect::<Vec<_>>();
+                tiles.extend(searcher.find_all(TEXT).map(|start| (start, start + pattern.len())));
+                tiles.sort();
+                let mut end = 0;
+                for &(start, next) in tiles.iter() {
+                    assert_eq!(start, end, "{:?}: {:?}", pattern, tiles);
+                    end = next;
+                }
+                assert_eq!(end, TEXT.len());
+            }
+        }
+        
+        #[test]
+        fn for_each_match() {
+            let mut searcher = BMHPattern::new("e");
+            let mut hits = Vec::new();
+            searcher.for_each_match(TEXT, |start| hits.push(start));
+            assert_eq!(hits, searcher.find_all(TEXT).collect::<Vec<_>>());
+            
+            let flow = searcher.try_for_each_match(TEXT, |start| {
+                if start > 10 { ControlFlow::Break(start) } else { ControlFlow::Continue(()) }
+            });
+            assert_eq!(flow, ControlFlow::Break(12));
+            
+            let mut searcher = BMHPattern::new("");
+            searcher.for_each_match(TEXT, |_| panic!("the empty pattern has no matches"));
+        }
+        
+        #[test]
+        fn as_ref_inputs() {
+            let owned = String::from("dead");
+            let cow: Cow<str> = Cow::Owned(String::from("dead"));
+            let text = String::from(TEXT);
+            let bytes: &[u8] = TEXT.as_bytes();
+            let text_cow: Cow<[u8]> = Cow::Borrowed(bytes);
+            let vec = bytes.to_vec();
+            
+            for mut searcher in [BMHPattern::new(&owned), BMHPattern::new(&cow), BMHPattern::new("dead")] {
+                assert_eq!(searcher.bmh(&text), Some(16));
+                assert_eq!(searcher.bmh(bytes), Some(16));
+                assert_eq!(searcher.bmh(&text_cow), Some(16));
+                assert_eq!(searcher.bmh(&vec), Some(16));
+                assert_eq!(searcher.kmp(&vec), Some(16));
+                assert_eq!(searcher.linear(&text), Some(16));
+                assert!(searcher.contains(&text_cow));
+                assert_eq!(searcher.find_match(&text).map(|m| m.start), Some(16));
+            }
+            
+            let mut searcher = BMHBytesPattern::new(&vec[16..20]);
+            assert_eq!(searcher.bmh(&text), Some(16));
+            let mut searcher = BMHBytesPattern::new(&owned);
+            assert_eq!(searcher.bmh(&text_cow), Some(16));
+        }
+        
+        #[test]
+        fn find_in_each() {
+            let lines = ["the dog", "a dead dog", "", "no cats", "dogdog"];
+            let mut searcher = BMHPattern::new("dog");
+            let want = lines.iter().map(|line| BMHPattern::new("dog").bmh(line)).collect::<Vec<_>>();
+            assert_eq!(want, vec![Some(4), Some(7), None, None, Some(0)]);
+            assert_eq!(searcher.find_in_each(lines.iter().cloned()), want);
+            assert!(searcher.u8_bmh.bad_char_table.is_some());
+            
+            let text = String::from(TEXT);
+            assert_eq!(searcher.find_in_each(TEXT.split(' ')), vec![None, Some(0), None, None, None, None]);
+            assert_eq!(searcher.find_in_each(vec![text.as_str()]), vec![Some(4)]);
+            assert_eq!(searcher.find_in_each(Vec::new()), vec![]);
+        }
+        
+        #[test]
+        fn starts_and_ends_with() {
+            assert!(BMHPattern::new("the").starts_with(TEXT));
+            assert!(!BMHPattern::new("the").ends_with(TEXT));
+            assert!(BMHPattern::new("then").ends_with(TEXT));
+            assert!(!BMHPattern::new("then").starts_with(TEXT));
+            assert!(BMHPattern::new(TEXT).starts_with(TEXT));
+            assert!(BMHPattern::new(TEXT).ends_with(TEXT));
+            // The pattern is in the text, but not at either end.
+            assert!(!BMHPattern::new("dog").starts_with(TEXT));
+            assert!(!BMHPattern::new("dog").ends_with(TEXT));
+            // A pattern longer than the text can't be at either end.
+            assert!(!BMHPattern::new("then!").ends_with("then"));
+            assert!(!BMHPattern::new("then!").starts_with("then"));
+            assert!(BMHPattern::new("").starts_with(""));
+            assert!(BMHPattern::new("").ends_with(TEXT));
+        }
+        
+        #[test]
+        fn find_at_edges() {
+            assert_eq!(BMHPattern::new("--").find_at_edges("--a--"), (true, true));
+            assert_eq!(BMHPattern::new("the").find_at_edges(TEXT), (true, false));
+            assert_eq!(BMHPattern::new("then").find_at_edges(TEXT), (false, true));
+            assert_eq!(BMHPattern::new("dog").find_at_edges(TEXT), (false, false));
+            // The same occurrence can be at both edges.
+            assert_eq!(BMHPattern::new(TEXT).find_at_edges(TEXT), (true, true));
+            assert_eq!(BMHPattern::new("aba").find_at_edges("ababa"), (true, true));
+            assert_eq!(BMHPattern::new("then!").find_at_edges("then"), (false, false));
+            assert_eq!(BMHPattern::new("").find_at_edges(""), (true, true));
+        }
+        
+        #[test]
+        fn search_chars() {
+            for &(want, pattern) in CASES.iter() {
+                assert_eq!(BMHPattern::new(pattern).search_chars(TEXT), want);
+            }
+            // "é" and "ü" are two bytes each, and "日本" six together.
+            let text = "café über 日本語 menu";
+            let mut searcher = BMHPattern::new("über");
+            assert_eq!(searcher.search_chars(text), Some(5));
+            assert_eq!(searcher.bmh(text), Some(6));
+            let mut searcher = BMHPattern::new("語 m");
+            assert_eq!(searcher.search_chars(text), Some(12));
+            assert_eq!(searcher.search_chars(text), searcher.bmh_char_index(text));
+            assert_eq!(BMHPattern::new("本日").search_chars(text), None);
+            assert_eq!(BMHPattern::new("").search_chars(text), Some(0));
+            // Overlapping partial matches still need the borders.
+            assert_eq!(BMHPattern::new("ééa").search_chars("éééa"), Some(1));
+        }
+        
+        #[test]
+        fn raita() {
+            for &(want, pattern) in CASES.iter() {
+                let mut searcher = BMHPattern::new(pattern);
+                assert_eq!(searcher.raita(TEXT), want);
+                assert_eq!(searcher.raita(TEXT), searcher.bmh(TEXT));
+            }
+            assert_eq!(BMHPattern::new("").raita(""), Some(0));
+            assert_eq!(BMHPattern::new("a").raita("ba"), Some(1));
+            
+            let mut random = Random(0x9e3779b97f4a7c15);
+            for _ in 0..10000 {
+                let pattern = String::from_utf8(random.bytes(6, 3)).unwrap();
+                let text = random.bytes(20, 3);
+                let mut searcher = BMHPattern::new(&pattern);
+                assert_eq!(searcher.raita(&text), searcher.bmh(&text), "{:?} in {:?}", pattern, text);
+            }
+        }
+        
+        #[test]
+        fn raita_selective_middle() {
+            // Every window matches at both ends, but none in the middle,
+            // which BMH only finds after comparing half the pattern.
+            let pattern = "aaabaaa";
+            let text = "a".repeat(1000) + pattern;
+            let bad_char_table = bad_character_table(pattern);
+            
+            let mut bmh_comparisons = 0;
+            let bmh = bmh_search_by(pattern.as_bytes(), text.as_bytes(), &bad_char_table, |a, b| {
+                bmh_comparisons += 1;
+                a == b
+            });
+            let mut raita_comparisons = 0;
+            let raita = raita_search_by(pattern.as_bytes(), text.as_bytes(), &bad_char_table, |a, b| {
+                raita_comparisons += 1;
+                a == b
+            });
+            
+            assert_eq!(bmh, Some(1000));
+            assert_eq!(raita, bmh);
+            assert!(raita_comparisons < bmh_comparisons,
+                    "Raita made {} comparisons, BMH made {}", raita_comparisons, bmh_comparisons);
+        }
+        
+        #[test]
+        fn match_indices() {
+            let mut searcher = BMHPattern::new("e");
+            let indices = searcher.match_indices(TEXT).collect::<Vec<_>>();
+            assert_eq!(TEXT.match_indices("e").collect::<Vec<_>>(), indices);
+            assert_eq!(indices, vec![(2, "e"), (12, "e"), (17, "e"), (23, "e")]);
+            let starts = indices.iter().map(|&(start, _)| start).collect::<Vec<_>>();
+            assert_eq!(starts, searcher.find_all(TEXT).collect::<Vec<_>>());
+            
+            // The slices borrow from the text, so they outlive the pattern.
+            let text = String::from("dead or dead");
+            let slices = {
+                let pattern = String::from("dead");
+                let mut searcher = BMHPattern::new(&pattern);
+                searcher.match_indices(&text).map(|(_, s)| s).collect::<Vec<_>>()
+            };
+            assert_eq!(slices, vec!["dead", "dead"]);
+            assert_eq!(slices[1].as_ptr(), text[8..].as_ptr());
+            
+            assert_eq!(BMHPattern::new("").match_indices(TEXT).next(), None);
+        }
+        
+        #[test]
+        fn try_find() {
+            for &(want, pattern) in CASES.iter() {
+                let want = if pattern.is_empty() { Err(SearchError::EmptyPattern) } else { Ok(want) };
+                assert_eq!(BMHPattern::new(pattern).try_find(TEXT), want);
+            }
+            assert_eq!(BMHPattern::new("dog").try_find(TEXT), Ok(Some(4)));
+            assert_eq!(BMHPattern::new("").try_find(""), Err(SearchError::EmptyPattern));
+            assert_eq!(SearchError::EmptyPattern.to_string(), "the pattern is empty");
+        }
+        
+        #[test]
+        fn bmh_with_sentinel() {
+            for &(want, pattern) in CASES.iter() {
+                let mut text = TEXT.as_bytes().to_vec();
+                let mut searcher = BMHPattern::new(pattern);
+                assert_eq!(searcher.bmh_with_sentinel(&mut text), want);
+                assert_eq!(text, TEXT.as_bytes());
+                assert_eq!(searcher.bmh_with_sentinel(&mut text), searcher.bmh(&text));
+            }
+            
+            // Matches which would run into the sentinel don't count.
+            let mut text = b"xxab".to_vec();
+            assert_eq!(BMHPattern::new("abab").bmh_with_sentinel(&mut text), None);
+            assert_eq!(text, b"xxab");
+            let mut text = Vec::new();
+            assert_eq!(BMHPattern::new("a").bmh_with_sentinel(&mut text), None);
+            assert_eq!(BMHPattern::new("").bmh_with_sentinel(&mut text), Some(0));
+            assert!(text.is_empty());
+        }
+        
+        #[test]
+        fn rbmh() {
+            for &(want, pattern) in CASES.iter() {
+                let mut searcher = BMHPattern::new(pattern);
+                let want = want.map(|_| searcher.find_all(TEXT).max().unwrap());
+                assert_eq!(searcher.rbmh(TEXT), want);
+            }
+            
+            // "e" is repeated, so the last match isn't the first.
+            let mut searcher = BMHPattern::new("e");
+            assert_eq!(searcher.rbmh(TEXT), Some(23));
+            assert_eq!(searcher.rbmh(TEXT), searcher.find_all(TEXT).max());
+            assert_eq!(BMHPattern::new("aa").rbmh("aaaa"), Some(2));
+            assert_eq!(BMHPattern::new("ab").rbmh("a"), None);
+            assert_eq!(BMHPattern::new("").rbmh(TEXT), Some(TEXT.len()));
+        }
+        
+        #[test]
+        fn rbmh_agrees_with_rfind() {
+            let mut random = Random(0x853c49e6748fea9b);
+            for _ in 0..10000 {
+                let pattern = String::from_utf8(random.bytes(6, 3)).unwrap();
+                let text = String::from_utf8(random.bytes(20, 3)).unwrap();
+                assert_eq!(BMHPattern::new(&pattern).rbmh(&text), text.rfind(pattern.as_str()),
+                           "{:?} in {:?}", pattern, text);
+            }
+        }
+        
+        #[test]
+        fn compile() {
+            for &(want, pattern) in CASES.iter() {
+                assert_eq!(BMHPattern::new(pattern).compile().find(TEXT), want);
+                // A table which was already built is kept.
+                let mut searcher = BMHPattern::new(pattern);
+                searcher.bmh(TEXT);
+                assert_eq!(searcher.compile().find(TEXT), want);
+            }
+            
+            let compiled = Arc::new(BMHPattern::new("dead").compile());
+            let threads = ["dead", "the dog is very dead", "alive", "deadly dead"].iter().map(|&text| {
+                let compiled = Arc::clone(&compiled);
+                thread::spawn(move || compiled.find(text))
+            }).collect::<Vec<_>>();
+            let found = threads.into_iter().map(|thread| thread.join().unwrap()).collect::<Vec<_>>();
+            assert_eq!(found, vec![Some(0), Some(16), None, Some(0)]);
+        }
+        
+        #[test]
+        fn missing_pattern_bytes() {
+            // There's no "v" in the text, so the search stops
+            // before it has built the bad character table.
+            let mut searcher = BMHPattern::new("dove");
+            assert_eq!(searcher.bmh("the dog is dead then"), None);
+            assert!(searcher.u8_bmh.bad_char_table.is_none());
+            // Nor is there an "o" after the "v".
+            assert_eq!(searcher.bmh(TEXT), None);
+            assert!(searcher.u8_bmh.bad_char_table.is_none());
+            // There's an "x", the rarest byte, but no "a".
+            let mut searcher = BMHPattern::new("xa");
+            assert_eq!(searcher.bmh("xbxbxbxb"), None);
+            assert!(searcher.u8_bmh.bad_char_table.is_none());
+            // Every byte is there, but not as a match.
+            let mut searcher = BMHPattern::new("dove");
+            assert_eq!(searcher.bmh("vole, doe"), None);
+            assert!(searcher.u8_bmh.bad_char_table.is_some());
+
+            // The search starts just far enough before the first "v"
+            // to find a match ending with it.
+            let mut searcher = BMHPattern::new("ev");
+            assert_eq!(searcher.bmh("eev ev"), Some(1));
+            let mut searcher = BMHPattern::new("vv");
+            assert_eq!(searcher.bmh("vavv"), Some(2));
+
+            // Patterns which are too long don't look first, but
+            // the search still finds nothing.
+            let long = "the dog is very";
+            let mut searcher = BMHPattern::new(long);
+            assert_eq!(searcher.bmh("the dog is dead"), None);
+            assert!(searcher.u8_bmh.bad_char_table.is_some());
+        }
+        
+        #[test]
+        fn single_byte_pattern() {
+            let mut searcher = BMHPattern::new("e");
+            assert_eq!(searcher.bmh(TEXT), Some(2));
+            assert_eq!(searcher.kmp(TEXT), Some(2));
+            assert_eq!(searcher.bmh("dog"), None);
+            assert_eq!(searcher.kmp("dog"), None);
+            assert_eq!(searcher.bmh(""), None);
+            // Neither table was needed.
+            assert!(searcher.u8_bmh.bad_char_table.is_none());
+            assert!(searcher.u8_kmp.borders.is_none());
+            
+            let text = TEXT.chars().collect::<Vec<_>>();
+            for c in "the dog is very dead then xyz".chars() {
+                let pattern = c.to_string();
+                assert_eq!(BMHPattern::new(&pattern).bmh(TEXT), TEXT.find(c));
+                let pattern = [c];
+                assert_eq!(KMPPattern::new(&pattern[..]).kmp(&text[..]), text.iter().position(|&t| t == c));
+            }
+        }
+        
+        #[test]
+        fn periodic_pattern() {
+            // Every shift of "aaaa" lines it up with itself, so KMP's
+            // borders are all as long as they can be, and BMH's table
+            // has zero for "a", which has to shift by one, not zero.
+            let mut searcher = BMHPattern::new("aaaa");
+            assert_eq!(searcher.u8_kmp.borders(), &[0, 1, 2, 3]);
+            assert_eq!(searcher.cached_bad_char_table()[b'a' as usize], 0);
+            for &(want, text) in [(Some(1), "baaaaab"), (Some(0), "aaaa"), (None, "baaab"),
+                                  (None, "aaabaaab"), (Some(4), "aaabaaaa")].iter() {
+                assert_eq!(searcher.linear(text), want, "{:?}", text);
+                assert_eq!(searcher.kmp(text), want, "{:?}", text);
+                assert_eq!(searcher.bmh(text), want, "{:?}", text);
+                assert_eq!(searcher.raita(text), want, "{:?}", text);
+                assert_eq!(searcher.search_chars(text), want, "{:?}", text);
+            }
+            assert_eq!(searcher.find_all_vec("baaaaab"), vec![1]);
+            assert_eq!(searcher.find_all_vec("aaaaaaaaa"), vec![0, 4]);
+            assert_eq!(searcher.u8_kmp.find_overlapping(b"baaaaab").collect::<Vec<_>>(), vec![1, 2]);
+        }
+        
+        #[test]
+        fn find_all_vec() {
+            assert_eq!(BMHPattern::new("e").find_all_vec(TEXT), vec![2, 12, 17, 23]);
+            assert_eq!(BMHPattern::new("aa").find_all_vec("aaaaa"), vec![0, 2]);
+            assert!(BMHPattern::new("").find_all_vec(TEXT).is_empty());
+        }
+        
+        #[test]
+        fn find_up_to() {
+            let mut searcher = BMHPattern::new("e");
+            assert_eq!(searcher.find_up_to(TEXT, 2), vec![2, 12]);
+            assert_eq!(searcher.find_up_to(TEXT, 4), vec![2, 12, 17, 23]);
+            assert_eq!(searcher.find_up_to(TEXT, 100), vec![2, 12, 17, 23]);
+            assert_eq!(searcher.find_up_to(TEXT, 0), Vec::<usize>::new());
+            
+            // A million matches, but only the first three are looked for.
+            let text = TEXT.repeat(1_000_000);
+            let mut searcher = BMHPattern::new("dog");
+            assert_eq!(searcher.find_up_to(&text, 3), vec![4, 29, 54]);
+        }
+        
+        #[test]
+        fn find_all_is_lazy() {
+            let text = TEXT.repeat(100_000);
+            let mut searcher = BMHPattern::new("dog");
+            assert_eq!(searcher.find_all(&text).take(3).collect::<Vec<_>>(), vec![4, 29, 54]);
+        }
+        
+        #[test]
+        fn bmh_high_bytes() {
+            // Every byte, up to and including 0xFF, needs a slot.
+            assert_eq!(bad_character_table("dog").len(), 256);
+
+            let text = "the \u{00FF} dog is dead";
+            let mut searcher = BMHPattern::new("dog");
+            assert_eq!(searcher.bmh(text), Some(7));
+            let mut searcher = BMHPattern::new("\u{00FF} dog");
+            assert_eq!(searcher.bmh(text), Some(4));
+        }
+    }
+}
+
+// Only the `alloc` parts of the API are used here, so these
+// run against the same code as a `no_std` build.
+#[cfg(all(test, not(feature = "std")))]
+mod no_std {
+    use alloc::vec::Vec;
+    use super::{kmp_search, border_table, bmh_search, bad_character_table};
+    use super::{KMPPattern, BMHPattern, AhoCorasick, RabinKarpPattern};
+    use correct_return::{CASES, TEXT};
+
+    #[test]
+    fn core_api() {
+        for &(want, pattern) in CASES.iter() {
+            let bytes = pattern.as_bytes();
+            assert_eq!(kmp_search(bytes, TEXT.as_bytes(), &border_table(bytes)), want);
+            assert_eq!(bmh_search(pattern, TEXT, &bad_character_table(pattern)), want);
+            assert_eq!(KMPPattern::new(bytes).kmp(TEXT.as_bytes()), want);
+            assert_eq!(BMHPattern::new(pattern).bmh(TEXT), want);
+            assert_eq!(RabinKarpPattern::new(bytes).rabin_karp(TEXT.as_bytes()), want);
+            assert_eq!(AhoCorasick::new(&[pattern]).find_first(TEXT), want.map(|i| (i, 0)));
+        }
+        let mut searcher = BMHPattern::new("e");
+        assert_eq!(searcher.find_all(TEXT).collect::<Vec<_>>(), [2, 12, 17, 23]);
+        assert_eq!(searcher.replace_all("eel", "a"), "aal");
     }
 }
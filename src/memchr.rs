@@ -0,0 +1,166 @@
+// Finds the first `needle` in the haystack, like the `memchr` C function.
+#[cfg(not(feature = "simd"))]
+pub fn memchr(needle: u8, haystack: &[u8]) -> Option<usize> {
+    haystack.iter().position(|&b| b == needle)
+}
+
+// Finds the first `needle` in the haystack, like the `memchr` C function.
+// This checks eight bytes at a time by treating them as one `u64`,
+// which works on any target and needs no `unsafe`.
+#[cfg(feature = "simd")]
+pub fn memchr(needle: u8, haystack: &[u8]) -> Option<usize> {
+    const LOW_BITS: u64 = 0x0101_0101_0101_0101;
+    const HIGH_BITS: u64 = 0x8080_8080_8080_8080;
+    let repeated = LOW_BITS * needle as u64;
+
+    let chunks = haystack.chunks_exact(8);
+    let rest = haystack.len() - chunks.remainder().len();
+    for (i, chunk) in chunks.enumerate() {
+        let mut word = [0; 8];
+        word.copy_from_slice(chunk);
+        // The bytes equal to the needle become zero, and subtracting
+        // one from a zero byte sets its high bit. Bytes above a zero
+        // byte can also end up with their high bit set by the borrow,
+        // but the lowest set bit is always the first zero byte.
+        let x = u64::from_le_bytes(word) ^ repeated;
+        let zeros = x.wrapping_sub(LOW_BITS) & !x & HIGH_BITS;
+        if zeros != 0 {
+            return Some(i * 8 + zeros.trailing_zeros() as usize / 8);
+        }
+    }
+    haystack[rest..].iter().position(|&b| b == needle).map(|i| rest + i)
+}
+
+// Finds the first byte of the text which is in the set, like
+// `str::find` with a slice of chars. The set is meant to be ASCII;
+// other bytes can match inside a char, which isn't a char boundary.
+pub fn find_first_of(text: &str, set: &[u8]) -> Option<usize> {
+    let in_set = membership_table(set);
+    text.bytes().position(|b| in_set[b as usize])
+}
+
+// `find_first_of`, but for the last byte in the set, like `str::rfind`.
+pub fn find_last_of(text: &str, set: &[u8]) -> Option<usize> {
+    let in_set = membership_table(set);
+    text.bytes().rposition(|b| in_set[b as usize])
+}
+
+// The byte of the pattern least likely to be in a text, and where it
+// last is in the pattern. Any match has that byte at that position at
+// the latest, so no match starts more than that far before the first
+// of them in the text.
+pub fn rarest_byte(pattern: &[u8]) -> (u8, usize) {
+    let (offset, &rarest) = pattern.iter().enumerate().rev()
+        .min_by_key(|&(_, &b)| commonness(b))
+        .expect("the pattern must not be empty");
+    (rarest, offset)
+}
+
+// A set of bytes, one bit for each of the 256.
+pub fn byte_set(bytes: &[u8]) -> [u64; 4] {
+    let mut set = [0u64; 4];
+    for &b in bytes {
+        set[b as usize / 64] |= 1 << (b % 64);
+    }
+    set
+}
+
+// Whether every byte in the set is somewhere in the text. This stops
+// as soon as it has seen them all, which for a short pattern in a text
+// which has a match is usually well before the end.
+pub fn contains_all(text: &[u8], set: &[u64; 4]) -> bool {
+    let mut missing = *set;
+    let mut left = set.iter().map(|word| word.count_ones()).sum::<u32>();
+    for &b in text {
+        if left == 0 {
+            break;
+        }
+        let (word, bit) = (b as usize / 64, 1 << (b % 64));
+        if missing[word] & bit != 0 {
+            missing[word] &= !bit;
+            left -= 1;
+        }
+    }
+    left == 0
+}
+
+// A rough guess at how common the byte is in text, mostly English,
+// from 0 for the rarest. Only the order matters.
+fn commonness(b: u8) -> u8 {
+    match b {
+        b' ' => 7,
+        b'e' | b't' | b'a' | b'o' | b'i' | b'n' | b's' | b'h' | b'r' => 6,
+        b'a'..=b'z' => 5,
+        b'\n' | b',' | b'.' | b'0'..=b'9' => 4,
+        b'A'..=b'Z' => 3,
+        0x21..=0x7e | b'\t' | b'\r' => 2,
+        // Continuation bytes are in every char of most other scripts.
+        0x80..=0xbf => 1,
+        _ => 0,
+    }
+}
+
+fn membership_table(set: &[u8]) -> [bool; 256] {
+    let mut in_set = [false; 256];
+    for &b in set {
+        in_set[b as usize] = true;
+    }
+    in_set
+}
+
+#[cfg(test)]
+mod correct_return {
+    use super::{memchr, find_first_of, find_last_of, rarest_byte, byte_set, contains_all};
+    use correct_return::{Random, TEXT};
+
+    #[test]
+    fn memchr_agrees_with_position() {
+        let mut random = Random(0x2545f4914f6cdd1d);
+        for _ in 0..10000 {
+            let haystack = random.bytes(40, 4);
+            let needle = random.below(5) as u8 + b'a';
+            assert_eq!(memchr(needle, &haystack), haystack.iter().position(|&b| b == needle),
+                       "{:?} in {:?}", needle, haystack);
+        }
+        assert_eq!(memchr(0, &[]), None);
+        assert_eq!(memchr(0xff, &[0x7f; 20]), None);
+        assert_eq!(memchr(0x00, &[0x01, 0x00, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x00]), Some(1));
+        assert_eq!(memchr(0x80, &[0x00; 17]), None);
+        assert_eq!(memchr(0x80, &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x80]), Some(8));
+    }
+    #[test]
+    fn first_and_last_of() {
+        let vowels = b"aeiou";
+        assert_eq!(find_first_of(TEXT, vowels), Some(2));
+        assert_eq!(find_last_of(TEXT, vowels), Some(23));
+        assert_eq!(find_first_of(TEXT, b"xyz"), Some(14));
+        assert_eq!(find_last_of(TEXT, b"xyz"), Some(14));
+        assert_eq!(find_first_of(TEXT, b""), None);
+        assert_eq!(find_last_of("", vowels), None);
+        // Byte offsets, like `str::find` and `str::rfind`.
+        let chars = ['a', 'e', 'i', 'o', 'u'];
+        assert_eq!(find_first_of("ça va", vowels), "ça va".find(&chars[..]));
+        assert_eq!(find_last_of("ça va", vowels), "ça va".rfind(&chars[..]));
+    }
+
+    #[test]
+    fn rarest() {
+        assert_eq!(rarest_byte(b"xhe"), (b'x', 0));
+        assert_eq!(rarest_byte(b"Quiz"), (b'Q', 0));
+        // Ties go to the last one, since it's the furthest the
+        // match can start before it.
+        assert_eq!(rarest_byte(b"zaz"), (b'z', 2));
+        assert_eq!(rarest_byte("caf\u{e9}".as_bytes()), (0xc3, 3));
+    }
+
+    #[test]
+    fn byte_sets() {
+        let set = byte_set(b"dead\x00\xff");
+        assert_eq!(set, [1, 1 << (b'a' - 64) | 1 << (b'd' - 64) | 1 << (b'e' - 64), 0, 1 << 63]);
+        assert!(contains_all(b"\xff a deed \x00", &set));
+        assert!(!contains_all(b"\xff a deed", &set));
+        assert!(!contains_all(b"", &set));
+        assert!(contains_all(b"", &byte_set(b"")));
+        assert!(contains_all(TEXT.as_bytes(), &byte_set(TEXT.as_bytes())));
+    }
+}
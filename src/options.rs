@@ -0,0 +1,205 @@
+use super::{is_word_boundary, BMHPattern};
+
+// A `BMHPattern` along with how to search for it, built up a flag at a
+// time, e.g. `BMHPattern::new("the").case_insensitive(true).find(text)`.
+// With every flag off, `find` is the same as `BMHPattern::bmh`.
+#[derive(Clone, Debug)]
+pub struct SearchOptions<'s> {
+    pattern: BMHPattern<'s>,
+    case_insensitive: bool,
+    whole_word: bool,
+    overlapping: bool,
+    anchored: bool,
+}
+
+impl<'s> SearchOptions<'s> {
+    pub fn new(pattern: BMHPattern<'s>) -> SearchOptions<'s> {
+        SearchOptions{
+            pattern,
+            case_insensitive: false,
+            whole_word: false,
+            overlapping: false,
+            anchored: false,
+        }
+    }
+
+    // Compares ASCII letters ignoring case, like `bmh_ignore_ascii_case`.
+    pub fn case_insensitive(mut self, yes: bool) -> SearchOptions<'s> {
+        self.case_insensitive = yes;
+        self
+    }
+
+    // Only matches which are whole words, like `find_word`.
+    pub fn whole_word(mut self, yes: bool) -> SearchOptions<'s> {
+        self.whole_word = yes;
+        self
+    }
+
+    // Lets the matches from `find_all` overlap.
+    pub fn overlapping(mut self, yes: bool) -> SearchOptions<'s> {
+        self.overlapping = yes;
+        self
+    }
+
+    // Only matches at the very start of the text.
+    pub fn anchored(mut self, yes: bool) -> SearchOptions<'s> {
+        self.anchored = yes;
+        self
+    }
+
+    pub fn find(&mut self, text: &str) -> Option<usize> {
+        self.find_from(text, 0)
+    }
+
+    pub fn find_all<'a>(&'a mut self, text: &'a str) -> SearchMatches<'a, 's> {
+        // As with `find_all`, an empty pattern has no matches.
+        let position = if self.pattern.pattern.is_empty() { None } else { Some(0) };
+        SearchMatches{
+            options: self,
+            text,
+            position,
+        }
+    }
+
+    fn find_from(&mut self, text: &str, mut start: usize) -> Option<usize> {
+        let len = self.pattern.pattern.len();
+        if self.anchored {
+            // Only the start of the text can match,
+            // so compare it once instead of searching.
+            if start > 0 {
+                return None;
+            }
+            let (head, pattern) = (text.as_bytes().get(..len)?, self.pattern.pattern.as_bytes());
+            let matched = if self.case_insensitive {
+                head.eq_ignore_ascii_case(pattern)
+            } else {
+                head == pattern
+            };
+            return if matched && self.is_whole_word(text, 0) { Some(0) } else { None };
+        }
+        loop {
+            if start > text.len() {
+                return None;
+            }
+            // A match can only start on a char boundary.
+            while !text.is_char_boundary(start) {
+                start += 1;
+            }
+
+            let rest = &text[start..];
+            let found = if self.case_insensitive {
+                self.pattern.bmh_ignore_ascii_case(rest)
+            } else {
+                self.pattern.bmh(rest)
+            };
+            let i = start + found?;
+            if self.is_whole_word(text, i) {
+                return Some(i);
+            }
+            start = i + 1;
+        }
+    }
+
+    // Whether the match at `i` passes the whole word check, if it's on.
+    fn is_whole_word(&self, text: &str, i: usize) -> bool {
+        // Only ASCII bytes can be word characters, so checking
+        // the neighbouring bytes is the same as checking chars.
+        let bytes = text.as_bytes();
+        let before = i.checked_sub(1).map(|j| bytes[j] as char);
+        let after = bytes.get(i + self.pattern.pattern.len()).map(|&b| b as char);
+        !self.whole_word || is_word_boundary(before, after)
+    }
+}
+
+pub struct SearchMatches<'a, 's: 'a> {
+    options: &'a mut SearchOptions<'s>,
+    text: &'a str,
+    // Where in the text to resume searching from, or `None`
+    // once there are no more matches.
+    position: Option<usize>,
+}
+
+impl<'a, 's> Iterator for SearchMatches<'a, 's> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        let found = self.options.find_from(self.text, self.position?);
+        let len = self.options.pattern.pattern.len();
+        let overlapping = self.options.overlapping;
+        self.position = found.map(|i| if overlapping { i + 1 } else { i + len });
+        found
+    }
+}
+
+#[cfg(test)]
+mod correct_return {
+    use std::vec::Vec;
+    use super::SearchOptions;
+    use BMHPattern;
+    use correct_return::{CASES, TEXT};
+
+    #[test]
+    fn defaults_match_bmh() {
+        for &(want, pattern) in CASES.iter() {
+            let mut options = SearchOptions::new(BMHPattern::new(pattern));
+            assert_eq!(options.find(TEXT), want);
+            let all = options.find_all(TEXT).collect::<Vec<_>>();
+            assert_eq!(all, BMHPattern::new(pattern).find_all(TEXT).collect::<Vec<_>>());
+        }
+        assert_eq!(SearchOptions::new(BMHPattern::new("")).find(TEXT), Some(0));
+        assert_eq!(SearchOptions::new(BMHPattern::new("")).find_all(TEXT).next(), None);
+    }
+
+    #[test]
+    fn combinations() {
+        let text = "The theme: then THE end, the_the the";
+        let find = |options: SearchOptions| -> Vec<usize> {
+            let mut options = options;
+            options.find_all(text).collect()
+        };
+
+        assert_eq!(find(BMHPattern::new("the").whole_word(false)), vec![4, 11, 25, 29, 33]);
+        assert_eq!(find(BMHPattern::new("the").case_insensitive(true)), vec![0, 4, 11, 16, 25, 29, 33]);
+        assert_eq!(find(BMHPattern::new("the").whole_word(true)), vec![33]);
+        assert_eq!(find(BMHPattern::new("the").case_insensitive(true).whole_word(true)), vec![0, 16, 33]);
+        assert_eq!(find(BMHPattern::new("the").case_insensitive(true).anchored(true)), vec![0]);
+        assert_eq!(find(BMHPattern::new("the").anchored(true)), Vec::<usize>::new());
+        assert_eq!(find(BMHPattern::new("the").case_insensitive(true).whole_word(true).anchored(true)), vec![0]);
+
+        assert_eq!(BMHPattern::new("THE").case_insensitive(true).whole_word(true).find(TEXT), Some(0));
+        assert_eq!(BMHPattern::new("DOG IS").case_insensitive(true).find(TEXT), Some(4));
+        assert_eq!(BMHPattern::new("he").whole_word(true).find(TEXT), None);
+    }
+
+    #[test]
+    fn overlapping() {
+        let mut options = BMHPattern::new("aa").overlapping(true);
+        assert_eq!(options.find_all("aaaa").collect::<Vec<_>>(), vec![0, 1, 2]);
+        let mut options = BMHPattern::new("aa").overlapping(false);
+        assert_eq!(options.find_all("aaaa").collect::<Vec<_>>(), vec![0, 2]);
+        let mut options = BMHPattern::new("AA").overlapping(true).case_insensitive(true).whole_word(true);
+        assert_eq!(options.find_all("aaa aa").collect::<Vec<_>>(), vec![4]);
+        // Overlapping matches step over multibyte chars.
+        let mut options = BMHPattern::new("éé").overlapping(true);
+        assert_eq!(options.find_all("ééé").collect::<Vec<_>>(), vec![0, 2]);
+    }
+
+    #[test]
+    fn anchored_only_compares_the_start() {
+        // Neither table is needed to tell that the text doesn't
+        // start with the pattern.
+        let mut options = BMHPattern::new("the end is near").anchored(true);
+        assert_eq!(options.find(TEXT), None);
+        assert!(options.pattern.u8_bmh.bad_char_table.is_none());
+        let mut options = BMHPattern::new("THE DOG IS VERY").case_insensitive(true).anchored(true);
+        assert_eq!(options.find(TEXT), Some(0));
+        assert!(options.pattern.ascii_case_bad_char_table.is_none());
+
+        let mut options = BMHPattern::new("the").whole_word(true).anchored(true);
+        assert_eq!(options.find("theme"), None);
+        assert_eq!(options.find("the end"), Some(0));
+        assert_eq!(options.find("th"), None);
+        assert_eq!(options.find_all("the the").collect::<Vec<_>>(), vec![0]);
+        assert_eq!(BMHPattern::new("").anchored(true).find(TEXT), Some(0));
+    }
+}
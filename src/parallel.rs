@@ -0,0 +1,137 @@
+use std::cmp;
+use std::thread;
+
+use super::{border_table, KMPMatches, KMPPattern};
+
+// Below this, starting a thread costs more than searching the chunk.
+const MIN_CHUNK_LEN: usize = 64 * 1024;
+
+impl<'s, C> KMPPattern<'s, C>
+    where C: PartialEq + Sync {
+
+    // `find_all`, but with the text split into chunks which are
+    // searched on their own threads. There's a thread for each core,
+    // as `available_parallelism` counts them, unless that would make
+    // the chunks shorter than `MIN_CHUNK_LEN`, so short texts are
+    // searched on fewer threads, down to just this one, without
+    // spawning any.
+    pub fn par_find_all(&self, text: &[C]) -> Vec<usize> {
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
+        let chunk_len = cmp::max(text.len().div_ceil(threads), MIN_CHUNK_LEN);
+        self.find_all_in_chunks(text, chunk_len)
+    }
+
+    fn find_all_in_chunks(&self, text: &[C], chunk_len: usize) -> Vec<usize> {
+        let pattern = self.pattern;
+        let m = pattern.len();
+        // An empty pattern gets no matches, as with `find_all`.
+        if m == 0 {
+            return Vec::new();
+        }
+
+        // This only borrows the pattern, so the borders can't be cached,
+        // but they're only read, so every thread can share them.
+        let generated;
+        let borders = match self.borders {
+            Some(ref b) => &b[..],
+            None => {
+                generated = border_table(pattern);
+                &generated[..]
+            },
+        };
+
+        // Each chunk overlaps the next by `m - 1` elements so it can find
+        // every match starting in it, and only those, including the ones
+        // which run into the next chunk.
+        let search_chunk = move |start: usize| {
+            let end = cmp::min(start + chunk_len + m - 1, text.len());
+            let matches = KMPMatches{
+                pattern,
+                borders,
+                text: &text[start..end],
+                position: 0,
+                matched: 0,
+                overlapping: true,
+            };
+            matches.map(|i| start + i).collect::<Vec<_>>()
+        };
+
+        // With only one chunk, a thread would just be waited on.
+        let chunks = if text.len() <= chunk_len {
+            vec![search_chunk(0)]
+        } else {
+            thread::scope(|scope| {
+                let handles = (0..text.len()).step_by(chunk_len).map(|start| {
+                    scope.spawn(move || search_chunk(start))
+                }).collect::<Vec<_>>();
+                handles.into_iter().map(|h| h.join().unwrap()).collect::<Vec<_>>()
+            })
+        };
+
+        // The chunks' matches are already in order, but they can overlap,
+        // so keep the same ones the sequential search would.
+        let mut next = 0;
+        chunks.into_iter().flatten().filter(|&start| {
+            let keep = start >= next;
+            if keep {
+                next = start + m;
+            }
+            keep
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod correct_return {
+    use super::super::KMPPattern;
+    use correct_return::{CASES, TEXT, Random};
+
+    #[test]
+    fn par_find_all() {
+        for &(_, pattern) in CASES.iter() {
+            let mut searcher = KMPPattern::new(pattern.as_bytes());
+            let text = TEXT.repeat(1000);
+            let want = searcher.find_all(text.as_bytes()).collect::<Vec<_>>();
+            assert_eq!(searcher.par_find_all(text.as_bytes()), want);
+            assert_eq!(KMPPattern::new(pattern.as_bytes()).par_find_all(text.as_bytes()), want);
+        }
+        assert_eq!(KMPPattern::new(b"").par_find_all(TEXT.as_bytes()), Vec::<usize>::new());
+        assert_eq!(KMPPattern::new(b"dog").par_find_all(b""), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn straddles_chunks() {
+        // With chunks of 18 bytes, "dead" starts in the first chunk
+        // but ends in the second.
+        let searcher = KMPPattern::new(b"dead");
+        assert_eq!(searcher.find_all_in_chunks(TEXT.as_bytes(), 18), vec![16]);
+        assert_eq!(searcher.find_all_in_chunks(TEXT.as_bytes(), 17), vec![16]);
+
+        // Matches overlapping a boundary are only kept once,
+        // and only when the sequential search would keep them.
+        let searcher = KMPPattern::new(b"aa");
+        for chunk_len in 1..8 {
+            assert_eq!(searcher.find_all_in_chunks(b"aaaaa", chunk_len), vec![0, 2]);
+        }
+        let text = "ab".repeat(50);
+        let mut searcher = KMPPattern::new(b"abab");
+        let want = searcher.find_all(text.as_bytes()).collect::<Vec<_>>();
+        for chunk_len in 1..text.len() + 2 {
+            assert_eq!(searcher.find_all_in_chunks(text.as_bytes(), chunk_len), want);
+        }
+    }
+
+    #[test]
+    fn agrees_with_find_all() {
+        let mut random = Random(0x2545f4914f6cdd1d);
+        for _ in 0..2000 {
+            let pattern = random.bytes(4, 2);
+            let text = random.bytes(40, 2);
+            let chunk_len = random.below(10) as usize + 1;
+            let mut searcher = KMPPattern::new(&pattern[..]);
+            let want = searcher.find_all(&text).collect::<Vec<_>>();
+            assert_eq!(searcher.find_all_in_chunks(&text, chunk_len), want,
+                       "{:?} in {:?} in chunks of {}", pattern, text, chunk_len);
+        }
+    }
+}
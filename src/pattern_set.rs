@@ -0,0 +1,65 @@
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use super::{bad_character_table_bytes, bmh_search_bytes};
+
+// A handful of patterns, each with its own bad character table,
+// for checking which of them occur in a text. This searches the text
+// once per pattern, which is fine for a few patterns; for many,
+// `AhoCorasick` searches for all of them at once.
+#[derive(Clone, Debug)]
+pub struct PatternSet<'s> {
+    patterns: Vec<(&'s str, Vec<usize>)>,
+}
+
+impl<'s> PatternSet<'s> {
+    pub fn new(patterns: &[&'s str]) -> PatternSet<'s> {
+        PatternSet{
+            patterns: patterns.iter()
+                .map(|&pattern| (pattern, bad_character_table_bytes(pattern.as_bytes())))
+                .collect(),
+        }
+    }
+
+    // Returns the index of the first pattern in the set which occurs
+    // anywhere in the text, which isn't necessarily the one which
+    // occurs first in the text.
+    pub fn contains_any(&self, text: &str) -> Option<usize> {
+        self.patterns.iter().position(|&(pattern, ref bad_char_table)| {
+            bmh_search_bytes(pattern.as_bytes(), text.as_bytes(), bad_char_table).is_some()
+        })
+    }
+}
+
+pub fn contains_any(patterns: &[&str], text: &str) -> Option<usize> {
+    PatternSet::new(patterns).contains_any(text)
+}
+
+#[cfg(test)]
+mod correct_return {
+    use super::{contains_any, PatternSet};
+    use correct_return::{CASES, TEXT};
+
+    #[test]
+    fn single_patterns() {
+        for &(want, pattern) in CASES.iter() {
+            assert_eq!(contains_any(&[pattern], TEXT), want.map(|_| 0));
+        }
+        assert_eq!(contains_any(&[], TEXT), None);
+    }
+
+    #[test]
+    fn contains_any_in_order() {
+        // Only the second pattern is in the text.
+        assert_eq!(contains_any(&["cat", "dog", "bird"], TEXT), Some(1));
+
+        // The set's order wins over the order in the text.
+        let set = PatternSet::new(&["then", "dog", "cat"]);
+        assert_eq!(set.contains_any(TEXT), Some(0));
+        assert_eq!(set.contains_any("a dog and a cat"), Some(1));
+        assert_eq!(set.contains_any("a cat"), Some(2));
+        assert_eq!(set.contains_any("a bird"), None);
+        // An empty pattern occurs in anything.
+        assert_eq!(contains_any(&["cat", ""], ""), Some(1));
+    }
+}
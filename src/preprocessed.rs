@@ -0,0 +1,144 @@
+#[cfg(not(feature = "std"))]
+use alloc::string::{String, ToString};
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::{bad_character_table, border_table, BMHPattern, KMPPattern};
+
+// A pattern along with its border table, owned so it can be stored,
+// e.g. generated at build time, and turned back into a `KMPPattern`
+// without running the preprocessing again.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PreprocessedKmp<C> {
+    pub pattern: Vec<C>,
+    pub borders: Vec<usize>,
+}
+
+impl<C> PreprocessedKmp<C>
+    where C: Clone + PartialEq {
+
+    pub fn new(pattern: &[C]) -> PreprocessedKmp<C> {
+        PreprocessedKmp{
+            pattern: pattern.to_vec(),
+            borders: border_table(pattern),
+        }
+    }
+}
+
+// Only copying the pattern in needs `Clone`, so a pattern of elements
+// which can't be cloned can still be built from its parts and searched.
+impl<C> PreprocessedKmp<C>
+    where C: PartialEq {
+
+    pub fn searcher(&self) -> KMPPattern<'_, C> {
+        // The borders are trusted, so at least check they could fit.
+        assert_eq!(self.borders.len(), self.pattern.len(),
+                   "there must be one border per pattern element");
+        KMPPattern::with_borders(&self.pattern[..], self.borders.clone())
+    }
+}
+
+// A pattern along with its bad character table, to be turned
+// back into a `BMHPattern` like a `PreprocessedKmp`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PreprocessedBmh {
+    pub pattern: String,
+    pub bad_char_table: Vec<usize>,
+}
+
+impl PreprocessedBmh {
+    pub fn new(pattern: &str) -> PreprocessedBmh {
+        PreprocessedBmh{
+            pattern: pattern.to_string(),
+            bad_char_table: bad_character_table(pattern),
+        }
+    }
+
+    pub fn searcher(&self) -> BMHPattern<'_> {
+        assert_eq!(self.bad_char_table.len(), 256,
+                   "there must be one shift per byte");
+        let mut searcher = BMHPattern::new(&self.pattern);
+        searcher.u8_bmh.bad_char_table = Some(self.bad_char_table.clone());
+        searcher
+    }
+}
+
+#[cfg(test)]
+mod correct_return {
+    use super::{PreprocessedKmp, PreprocessedBmh};
+    use correct_return::{CASES, TEXT};
+
+    #[test]
+    fn preprocessed_kmp() {
+        for &(want, pattern) in CASES.iter() {
+            let preprocessed = PreprocessedKmp::new(pattern.as_bytes());
+            // Storing and loading the parts should give the same pattern.
+            let loaded = PreprocessedKmp{
+                pattern: preprocessed.pattern.clone(),
+                borders: preprocessed.borders.clone(),
+            };
+            assert_eq!(loaded, preprocessed);
+
+            let mut searcher = loaded.searcher();
+            assert!(searcher.borders.is_some());
+            assert_eq!(searcher.kmp(TEXT.as_bytes()), want);
+        }
+        let preprocessed = PreprocessedKmp::new(b"");
+        assert_eq!(preprocessed.searcher().kmp(TEXT.as_bytes()), Some(0));
+    }
+
+    #[test]
+    fn preprocessed_bmh() {
+        for &(want, pattern) in CASES.iter() {
+            let preprocessed = PreprocessedBmh::new(pattern);
+            let loaded = PreprocessedBmh{
+                pattern: preprocessed.pattern.clone(),
+                bad_char_table: preprocessed.bad_char_table.clone(),
+            };
+            assert_eq!(loaded, preprocessed);
+
+            let mut searcher = loaded.searcher();
+            assert!(searcher.u8_bmh.bad_char_table.is_some());
+            assert_eq!(searcher.bmh(TEXT), want);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn json_round_trip() {
+        use serde_json;
+        use std::vec::Vec;
+
+        for &(want, pattern) in CASES.iter() {
+            let preprocessed = PreprocessedKmp::new(pattern.as_bytes());
+            let json = serde_json::to_string(&preprocessed).unwrap();
+            let loaded: PreprocessedKmp<u8> = serde_json::from_str(&json).unwrap();
+            assert_eq!(loaded, preprocessed);
+            assert_eq!(loaded.searcher().kmp(TEXT.as_bytes()), want, "{:?}", pattern);
+
+            let preprocessed = PreprocessedBmh::new(pattern);
+            let json = serde_json::to_string(&preprocessed).unwrap();
+            let loaded: PreprocessedBmh = serde_json::from_str(&json).unwrap();
+            assert_eq!(loaded, preprocessed);
+            assert_eq!(loaded.searcher().bmh(TEXT), want, "{:?}", pattern);
+        }
+
+        // The tables are stored as they are, not rebuilt on loading.
+        let json = r#"{"pattern":[97,98,97],"borders":[0,0,1]}"#;
+        let loaded: PreprocessedKmp<u8> = serde_json::from_str(json).unwrap();
+        assert_eq!(serde_json::to_string(&loaded).unwrap(), json);
+        assert_eq!(loaded.searcher().find_overlapping(b"ababa").collect::<Vec<_>>(), vec![0, 2]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn preprocessed_kmp_wrong_length() {
+        let preprocessed = PreprocessedKmp{ pattern: vec![b'a', b'b'], borders: vec![0] };
+        preprocessed.searcher();
+    }
+}
@@ -0,0 +1,212 @@
+use core::hash::{Hash, Hasher};
+
+use super::Searcher;
+
+// A Mersenne prime, so the reduced hashes use almost all of a `u64`.
+pub const DEFAULT_MODULUS: u64 = (1 << 61) - 1;
+pub const DEFAULT_BASE: u64 = 257;
+
+#[derive(Clone, Debug)]
+pub struct RabinKarpPattern<'s, C: 's> {
+    pattern: &'s [C],
+    base: u64,
+    modulus: u64,
+    // The hash of the pattern, and the base raised to one less
+    // than the length of the pattern, for removing the leading element.
+    hashes: Option<(u64, u64)>,
+}
+
+impl<'s, C> RabinKarpPattern<'s, C>
+    where C: Hash + PartialEq {
+
+    pub fn new(pattern: &'s [C]) -> RabinKarpPattern<'s, C> {
+        RabinKarpPattern::with_modulus(pattern, DEFAULT_MODULUS)
+    }
+
+    pub fn with_modulus(pattern: &'s [C], modulus: u64) -> RabinKarpPattern<'s, C> {
+        RabinKarpPattern::with_params(pattern, DEFAULT_BASE, modulus)
+    }
+
+    // The base and modulus only change how often different windows
+    // have the same hash as the pattern. Every window with the same hash
+    // is compared with the pattern, so the matches are always exact,
+    // but a small modulus, or a base which shares factors with it,
+    // means more comparisons. A Mersenne prime modulus like the default
+    // spreads the hashes out while staying cheap to reduce by.
+    // Any positive modulus works, up to `u64::MAX`.
+    pub fn with_params(pattern: &'s [C], base: u64, modulus: u64) -> RabinKarpPattern<'s, C> {
+        assert!(modulus > 0, "the modulus must be positive");
+        RabinKarpPattern{
+            pattern,
+            base: base % modulus,
+            modulus,
+            hashes: None,
+        }
+    }
+
+    pub fn rabin_karp(&mut self, text: &[C]) -> Option<usize> {
+        let m = self.pattern.len();
+        // Like `str::find`, an empty pattern matches at the start.
+        if m == 0 {
+            return Some(0);
+        }
+        if m > text.len() {
+            return None;
+        }
+
+        let (base, modulus) = (self.base, self.modulus);
+        let (pattern_hash, leading_power) = self.cached_hashes();
+
+        // Hash the first window of the text.
+        let mut hash = polynomial_hash(&text[..m], base, modulus);
+
+        // For each window of the text:
+        let mut t = 0;
+        loop {
+            // Hashes can collide, so only an actual comparison
+            // can confirm the match.
+            if hash == pattern_hash && text[t..t + m] == *self.pattern {
+                return Some(t);
+            }
+            if t + m == text.len() {
+                return None;
+            }
+
+            // Roll the hash forwards by removing the leading element
+            // and appending the next element of the text.
+            let leading = mul_mod(element_hash(&text[t], modulus), leading_power, modulus);
+            hash = sub_mod(hash, leading, modulus);
+            hash = add_mod(mul_mod(hash, base, modulus), element_hash(&text[t + m], modulus), modulus);
+            t += 1;
+        }
+    }
+
+    fn cached_hashes(&mut self) -> (u64, u64) {
+        match self.hashes {
+            Some(hashes) => hashes,
+            None => {
+                let (base, modulus) = (self.base, self.modulus);
+                let pattern_hash = polynomial_hash(self.pattern, base, modulus);
+                let mut leading_power = 1 % modulus;
+                for _ in 1..self.pattern.len() {
+                    leading_power = mul_mod(leading_power, base, modulus);
+                }
+                self.hashes = Some((pattern_hash, leading_power));
+                (pattern_hash, leading_power)
+            },
+        }
+    }
+}
+
+impl<'s, C> Searcher<C> for RabinKarpPattern<'s, C>
+    where C: Hash + PartialEq {
+
+    fn find(&mut self, text: &[C]) -> Option<usize> {
+        self.rabin_karp(text)
+    }
+}
+
+pub fn rabin_karp<C>(pattern: &[C], text: &[C]) -> Option<usize>
+    where C: Hash + PartialEq {
+    RabinKarpPattern::new(pattern).rabin_karp(text)
+}
+
+// Hashes the elements as the digits of a number in the given base.
+fn polynomial_hash<C: Hash>(elements: &[C], base: u64, modulus: u64) -> u64 {
+    elements.iter().fold(0, |hash, c| {
+        add_mod(mul_mod(hash, base, modulus), element_hash(c, modulus), modulus)
+    })
+}
+
+fn element_hash<C: Hash>(c: &C, modulus: u64) -> u64 {
+    let mut hasher = Fnv1a::new();
+    c.hash(&mut hasher);
+    hasher.finish() % modulus
+}
+
+// The 64 bit FNV-1a hash. `std`'s `DefaultHasher` isn't in `core`,
+// and the hashes only need to be spread out, not hard to predict.
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    fn new() -> Fnv1a {
+        Fnv1a(0xcbf29ce484222325)
+    }
+}
+
+impl Hasher for Fnv1a {
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 = (self.0 ^ b as u64).wrapping_mul(0x100000001b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+// `add_mod` and `sub_mod` take values which are already reduced.
+// With a modulus above 2^63 their sum can overflow a `u64`, so
+// the overflow is what tells whether to take the modulus off.
+fn add_mod(a: u64, b: u64, modulus: u64) -> u64 {
+    let (sum, overflowed) = a.overflowing_add(b);
+    if overflowed || sum >= modulus { sum.wrapping_sub(modulus) } else { sum }
+}
+
+fn sub_mod(a: u64, b: u64, modulus: u64) -> u64 {
+    if a >= b { a - b } else { a + (modulus - b) }
+}
+
+fn mul_mod(a: u64, b: u64, modulus: u64) -> u64 {
+    ((a as u128 * b as u128) % modulus as u128) as u64
+}
+
+#[cfg(test)]
+mod correct_return {
+    use std::vec::Vec;
+    use super::{rabin_karp, RabinKarpPattern};
+    use correct_return::{CASES, TEXT};
+
+    #[test]
+    fn rabin_karp_pattern() {
+        let text = TEXT.chars().collect::<Vec<_>>();
+        for &(want, pattern) in CASES.iter() {
+            let chars = pattern.chars().collect::<Vec<_>>();
+            let mut searcher = RabinKarpPattern::new(&chars[..]);
+            assert_eq!(searcher.rabin_karp(&text[..]), want);
+            assert_eq!(rabin_karp(&chars[..], &text[..]), want);
+        }
+    }
+
+    #[test]
+    fn collisions_are_verified() {
+        // With a modulus of 1 every window has the same hash,
+        // so every window has to be compared.
+        let text = TEXT.chars().collect::<Vec<_>>();
+        for &(want, pattern) in CASES.iter() {
+            let chars = pattern.chars().collect::<Vec<_>>();
+            let mut searcher = RabinKarpPattern::with_modulus(&chars[..], 1);
+            assert_eq!(searcher.rabin_karp(&text[..]), want);
+        }
+    }
+    #[test]
+    fn with_params() {
+        let text = TEXT.as_bytes();
+        // Tiny moduli and degenerate bases collide all the time,
+        // but the comparisons keep the results exact.
+        for &(base, modulus) in [(257, 2), (2, 2), (0, 7), (1, 3), (31, 1 << 61), (10, 97),
+                                  (31, u64::MAX), (u64::MAX - 1, u64::MAX), (257, (1 << 63) + 1)].iter() {
+            for &(want, pattern) in CASES.iter() {
+                let mut searcher = RabinKarpPattern::with_params(pattern.as_bytes(), base, modulus);
+                assert_eq!(searcher.rabin_karp(text), want, "{:?} with {} mod {}", pattern, base, modulus);
+            }
+        }
+        // Sums of hashes reduced by a modulus above 2^63 don't fit
+        // in a `u64`, and must still wrap around the modulus.
+        for &(want, pattern) in CASES.iter() {
+            let mut searcher = RabinKarpPattern::with_modulus(pattern.as_bytes(), u64::MAX);
+            assert_eq!(searcher.rabin_karp(text), want, "{:?}", pattern);
+        }
+    }
+}
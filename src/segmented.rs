@@ -0,0 +1,105 @@
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use super::border_table;
+
+// Searches text which is split into segments, e.g. the chunks of a rope,
+// without joining them. KMP never moves backwards in the text, so only
+// how much of the pattern is matched has to carry over between segments.
+#[derive(Clone, Debug)]
+pub struct SegmentedSearcher<'s> {
+    pattern: &'s [u8],
+    borders: Vec<usize>,
+}
+
+impl<'s> SegmentedSearcher<'s> {
+    pub fn new(pattern: &'s [u8]) -> SegmentedSearcher<'s> {
+        SegmentedSearcher{
+            pattern,
+            borders: border_table(pattern),
+        }
+    }
+
+    // Returns the offset of the first match in the segments
+    // as if they were one text.
+    pub fn find<'t, I>(&self, segments: I) -> Option<usize>
+        where I: IntoIterator<Item = &'t [u8]> {
+        let (pattern, borders) = (self.pattern, &self.borders[..]);
+        // Like `str::find`, an empty pattern matches at the start.
+        if pattern.is_empty() {
+            return Some(0);
+        }
+
+        // `p` is how much of the pattern matches the end of the text
+        // so far, and `offset` is where the current segment starts.
+        let mut p = 0;
+        let mut offset = 0;
+        for segment in segments {
+            for (i, &c) in segment.iter().enumerate() {
+                // Fall back through the borders until one can be
+                // extended by this byte, or none are left.
+                while p > 0 && pattern[p] != c {
+                    p = borders[p - 1];
+                }
+                if pattern[p] == c {
+                    p += 1;
+                }
+                if p == pattern.len() {
+                    return Some(offset + i + 1 - p);
+                }
+            }
+            offset += segment.len();
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod correct_return {
+    use std::vec::Vec;
+    use super::SegmentedSearcher;
+    use linear_search;
+    use correct_return::{CASES, TEXT, Random};
+
+    #[test]
+    fn whole_text() {
+        for &(want, pattern) in CASES.iter() {
+            let searcher = SegmentedSearcher::new(pattern.as_bytes());
+            assert_eq!(searcher.find(vec![TEXT.as_bytes()]), want);
+        }
+        assert_eq!(SegmentedSearcher::new(b"").find(Vec::new()), Some(0));
+        assert_eq!(SegmentedSearcher::new(b"a").find(vec![&b""[..], b""]), None);
+    }
+
+    #[test]
+    fn straddles_segments() {
+        let searcher = SegmentedSearcher::new(b"dead");
+        // Across two segments, and across three with an empty one.
+        assert_eq!(searcher.find(vec![&b"very de"[..], b"ad"]), Some(5));
+        assert_eq!(searcher.find(vec![&b"very d"[..], b"e", b"", b"ad"]), Some(5));
+        // A partial match at the end of a segment which doesn't
+        // continue has to fall back without losing the next match.
+        assert_eq!(searcher.find(vec![&b"ded"[..], b"ead"]), Some(2));
+        assert_eq!(searcher.find(vec![&b"d"[..], b"ede", b"ad"]), Some(2));
+        assert_eq!(searcher.find(vec![&b"dea"[..], b"d"]), Some(0));
+        assert_eq!(searcher.find(vec![&b"dea"[..], b"x", b"d"]), None);
+    }
+
+    #[test]
+    fn agrees_with_linear() {
+        let mut random = Random(0xda942042e4dd58b5);
+        for _ in 0..10000 {
+            let pattern = random.bytes(6, 3);
+            let text = random.bytes(20, 3);
+            // Cut the text at a few random places.
+            let mut cuts = (0..3).map(|_| random.below(text.len() as u64 + 1) as usize)
+                                 .collect::<Vec<_>>();
+            cuts.sort();
+            let segments = vec![&text[..cuts[0]], &text[cuts[0]..cuts[1]],
+                                &text[cuts[1]..cuts[2]], &text[cuts[2]..]];
+            assert_eq!(SegmentedSearcher::new(&pattern).find(segments.iter().cloned()),
+                       linear_search(&pattern, &text),
+                       "{:?} in {:?}", pattern, segments);
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use std::io::{self, Read};
+
+use super::BMHBytesPattern;
+
+pub const DEFAULT_BUFFER_SIZE: usize = 8 * 1024;
+
+// Searches a reader a buffer at a time, so the text never has to
+// be in memory all at once.
+#[derive(Clone, Debug)]
+pub struct StreamSearcher<'s> {
+    bmh: BMHBytesPattern<'s>,
+    pattern_len: usize,
+    buffer_size: usize,
+}
+
+impl<'s> StreamSearcher<'s> {
+    pub fn new(pattern: &'s [u8]) -> StreamSearcher<'s> {
+        StreamSearcher::with_buffer_size(pattern, DEFAULT_BUFFER_SIZE)
+    }
+
+    pub fn with_buffer_size(pattern: &'s [u8], buffer_size: usize) -> StreamSearcher<'s> {
+        assert!(buffer_size > 0, "the buffer size must be positive");
+        StreamSearcher{
+            bmh: BMHBytesPattern::new(pattern),
+            pattern_len: pattern.len(),
+            buffer_size,
+        }
+    }
+
+    // Returns the offset of the first match from wherever
+    // the reader was when it was passed in.
+    pub fn find<R: Read>(&mut self, mut reader: R) -> io::Result<Option<usize>> {
+        // Like `str::find`, an empty pattern matches at the start.
+        if self.pattern_len == 0 {
+            return Ok(Some(0));
+        }
+
+        // `window` holds the end of the previous read followed by the
+        // latest one, and `offset` is where it starts in the stream.
+        let mut window = Vec::with_capacity(self.pattern_len - 1 + self.buffer_size);
+        let mut offset = 0;
+        let mut chunk = vec![0; self.buffer_size];
+        loop {
+            let read = match reader.read(&mut chunk) {
+                Ok(0) => return Ok(None),
+                Ok(read) => read,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            window.extend_from_slice(&chunk[..read]);
+
+            if let Some(i) = self.bmh.bmh(&window) {
+                return Ok(Some(offset + i));
+            }
+
+            // A match straddling this read and the next starts within
+            // the last `len - 1` bytes, so only those need keeping.
+            let keep = std::cmp::min(window.len(), self.pattern_len - 1);
+            let dropped = window.len() - keep;
+            window.drain(..dropped);
+            offset += dropped;
+        }
+    }
+}
+
+#[cfg(test)]
+mod correct_return {
+    use std::io::{self, Cursor, Read};
+    use super::StreamSearcher;
+    use correct_return::{CASES, TEXT};
+
+    // Hands out at most one byte at a time, interrupting every other read.
+    struct Trickle<'a> {
+        bytes: &'a [u8],
+        interrupt: bool,
+    }
+
+    impl<'a> Read for Trickle<'a> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.interrupt = !self.interrupt;
+            if self.interrupt {
+                return Err(io::Error::new(io::ErrorKind::Interrupted, "again"));
+            }
+            if self.bytes.is_empty() || buf.is_empty() {
+                return Ok(0);
+            }
+            buf[0] = self.bytes[0];
+            self.bytes = &self.bytes[1..];
+            Ok(1)
+        }
+    }
+
+    #[test]
+    fn stream() {
+        for &(want, pattern) in CASES.iter() {
+            let mut searcher = StreamSearcher::new(pattern.as_bytes());
+            assert_eq!(searcher.find(Cursor::new(TEXT)).unwrap(), want);
+        }
+        let mut searcher = StreamSearcher::new(b"");
+        assert_eq!(searcher.find(Cursor::new("")).unwrap(), Some(0));
+    }
+
+    #[test]
+    fn straddles_buffers() {
+        // With a buffer of 3 bytes, "dead" is split across
+        // the reads of " de" and "ad ".
+        for size in 1..TEXT.len() + 2 {
+            for &(want, pattern) in CASES.iter() {
+                let mut searcher = StreamSearcher::with_buffer_size(pattern.as_bytes(), size);
+                assert_eq!(searcher.find(Cursor::new(TEXT)).unwrap(), want,
+                           "{:?} with a buffer of {}", pattern, size);
+            }
+        }
+    }
+
+    #[test]
+    fn short_reads() {
+        for &(want, pattern) in CASES.iter() {
+            let mut searcher = StreamSearcher::with_buffer_size(pattern.as_bytes(), 3);
+            let reader = Trickle{ bytes: TEXT.as_bytes(), interrupt: false };
+            assert_eq!(searcher.find(reader).unwrap(), want);
+        }
+    }
+}
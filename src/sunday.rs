@@ -0,0 +1,112 @@
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use super::Searcher;
+
+#[derive(Clone, Debug)]
+pub struct SundayPattern<'s> {
+    pattern: &'s str,
+    shift_table: Option<Vec<usize>>,
+}
+
+impl<'s> SundayPattern<'s> {
+    pub fn new(pattern: &'s str) -> SundayPattern<'s> {
+        SundayPattern{
+            pattern,
+            shift_table: None,
+        }
+    }
+
+    pub fn quick_search(&mut self, text: &str) -> Option<usize> {
+        let pattern = self.pattern.as_bytes();
+        let shift_table = self.cached_shift_table();
+
+        // Search the text using the pattern and shift table.
+        sunday_search(pattern, text.as_bytes(), shift_table)
+    }
+
+    fn cached_shift_table(&mut self) -> &[usize] {
+        // Generate the shift table using the pattern.
+        match self.shift_table {
+            None => {
+                self.shift_table = Some(sunday_shift_table(self.pattern.as_bytes()));
+                &self.shift_table.as_ref().unwrap()[..]
+            },
+            Some(ref s) => &s[..]
+        }
+    }
+}
+
+impl<'s> Searcher<u8> for SundayPattern<'s> {
+    fn find(&mut self, text: &[u8]) -> Option<usize> {
+        let pattern = self.pattern.as_bytes();
+        sunday_search(pattern, text, self.cached_shift_table())
+    }
+}
+
+// `shift_table[c]` is how far to shift the pattern when `c` is the
+// byte just after the window, which lines `c` up with its last
+// occurence in the pattern, or moves the pattern past it entirely.
+fn sunday_shift_table(pattern: &[u8]) -> Vec<usize> {
+    let mut shift_table = vec![pattern.len() + 1; 256];
+    for (i, &c) in pattern.iter().enumerate() {
+        shift_table[c as usize] = pattern.len() - i;
+    }
+    shift_table
+}
+
+fn sunday_search(pattern: &[u8], text: &[u8], shift_table: &[usize]) -> Option<usize> {
+    // Like `str::find`, an empty pattern matches at the start.
+    if pattern.is_empty() {
+        return Some(0);
+    }
+
+    let m = pattern.len();
+    let mut t = 0;
+    // While there's enough room in the text for the pattern:
+    while t + m <= text.len() {
+        if text[t..t + m] == *pattern {
+            return Some(t);
+        }
+        // The byte after the window is in the next window wherever
+        // it goes, so if there isn't one, there's no next window.
+        if t + m == text.len() {
+            return None;
+        }
+        t += shift_table[text[t + m] as usize];
+    }
+    None
+}
+
+#[cfg(test)]
+mod correct_return {
+    use std::string::String;
+    use super::SundayPattern;
+    use correct_return::{CASES, TEXT, Random};
+    use BMHPattern;
+
+    #[test]
+    fn quick_search() {
+        for &(want, pattern) in CASES.iter() {
+            let mut searcher = SundayPattern::new(pattern);
+            assert_eq!(searcher.quick_search(TEXT), want);
+        }
+        assert_eq!(SundayPattern::new("").quick_search(""), Some(0));
+        // The match is the last window, with nothing after it.
+        assert_eq!(SundayPattern::new("then").quick_search("then"), Some(0));
+        assert_eq!(SundayPattern::new("hen").quick_search("then"), Some(1));
+        assert_eq!(SundayPattern::new("thenx").quick_search("then"), None);
+    }
+
+    #[test]
+    fn agrees_with_bmh() {
+        let mut random = Random(0x2545f4914f6cdd1d);
+        for _ in 0..10000 {
+            let pattern = String::from_utf8(random.bytes(6, 3)).unwrap();
+            let text = String::from_utf8(random.bytes(20, 3)).unwrap();
+            assert_eq!(SundayPattern::new(&pattern).quick_search(&text),
+                       BMHPattern::new(&pattern).bmh(&text),
+                       "{:?} in {:?}", pattern, text);
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use core::cmp;
+
+// Searches for the pattern with the Two-Way algorithm, as in glibc's
+// `memmem`. Like KMP it never compares more than about twice the length
+// of the text, but it only needs a few numbers instead of a table.
+pub fn two_way(pattern: &[u8], text: &[u8]) -> Option<usize> {
+    // Like `str::find`, an empty pattern matches at the start.
+    if pattern.is_empty() {
+        return Some(0);
+    }
+    let m = pattern.len();
+
+    // Split the pattern at a critical factorization, where the period
+    // of the text around the split is the period of the whole pattern.
+    // Of the maximal suffixes under the two orderings of bytes,
+    // the later one starts at one.
+    let (a, a_period) = maximal_suffix(pattern, false);
+    let (b, b_period) = maximal_suffix(pattern, true);
+    let (split, mut period) = if a > b { (a, a_period) } else { (b, b_period) };
+
+    // If the part before the split repeats after one period, the period
+    // is the pattern's, and after a mismatch in the left part, the text
+    // matched is known to match the start of the pattern again.
+    // Otherwise, the period is long, and nothing needs remembering.
+    let periodic = period + split <= m && pattern[..split] == pattern[period..period + split];
+    if !periodic {
+        period = cmp::max(split, m - split) + 1;
+    }
+
+    let mut t = 0;
+    // How much of the start of the pattern is known to match at `t`.
+    let mut memory = 0;
+    'text:
+    while t + m <= text.len() {
+        // Compare the right part first, from the split forwards.
+        // A mismatch there means the pattern can't start before it.
+        for p in cmp::max(split, memory)..m {
+            if pattern[p] != text[t + p] {
+                t += p - split + 1;
+                memory = 0;
+                continue 'text;
+            }
+        }
+        // Then compare the left part, from the split backwards.
+        // A mismatch there shifts by the whole period.
+        for p in (memory..split).rev() {
+            if pattern[p] != text[t + p] {
+                t += period;
+                if periodic {
+                    memory = m - period;
+                }
+                continue 'text;
+            }
+        }
+        return Some(t);
+    }
+    None
+}
+
+// Finds the lexicographically largest suffix of `s`, or the smallest
+// when `reverse` is set, returning where it starts and its period.
+fn maximal_suffix(s: &[u8], reverse: bool) -> (usize, usize) {
+    // `s[start..]` is the best suffix so far, and `s[candidate..]` is
+    // being compared with it, having matched `offset` bytes of it.
+    let (mut start, mut candidate) = (0, 1);
+    let mut offset = 0;
+    let mut period = 1;
+    while candidate + offset < s.len() {
+        let (a, b) = (s[candidate + offset], s[start + offset]);
+        if (a < b && !reverse) || (a > b && reverse) {
+            // The candidate is worse, so everything up to the mismatch
+            // is one period of the best suffix.
+            candidate += offset + 1;
+            offset = 0;
+            period = candidate - start;
+        } else if a == b {
+            // Keep matching, skipping a whole period at a time.
+            if offset + 1 == period {
+                candidate += offset + 1;
+                offset = 0;
+            } else {
+                offset += 1;
+            }
+        } else {
+            // The candidate is better, so it's the best so far.
+            start = candidate;
+            candidate += 1;
+            offset = 0;
+            period = 1;
+        }
+    }
+    (start, period)
+}
+
+#[cfg(test)]
+mod correct_return {
+    use super::two_way;
+    use linear_search;
+    use correct_return::{CASES, TEXT, Random};
+
+    #[test]
+    fn two_way_fixtures() {
+        for &(want, pattern) in CASES.iter() {
+            assert_eq!(two_way(pattern.as_bytes(), TEXT.as_bytes()), want);
+        }
+        assert_eq!(two_way(b"", b""), Some(0));
+        assert_eq!(two_way(b"abcabcabcX", b"abcabcabcabcabcXabc"), Some(6));
+        assert_eq!(two_way(b"abcabcabcX", b"abcabcabcabcabcabc"), None);
+        assert_eq!(two_way(b"aaab", b"aaaaaaaaab"), Some(6));
+        assert_eq!(two_way(b"\xff\x00", b"\x00\xff\xff\x00"), Some(2));
+    }
+
+    #[test]
+    fn two_way_agrees_with_linear() {
+        let mut random = Random(0x2545f4914f6cdd1d);
+        for _ in 0..20000 {
+            let pattern = random.bytes(8, 3);
+            let text = random.bytes(30, 3);
+            assert_eq!(two_way(&pattern, &text), linear_search(&pattern, &text),
+                       "{:?} in {:?}", pattern, text);
+        }
+    }
+
+    #[test]
+    fn two_way_periodic() {
+        // Texts made of repeats of the start of the pattern,
+        // with the odd byte changed, keep nearly matching.
+        let mut random = Random(0x9e3779b97f4a7c15);
+        for _ in 0..5000 {
+            let unit = random.bytes(3, 2);
+            if unit.is_empty() {
+                continue;
+            }
+            let repeats = random.below(5) as usize + 1;
+            let mut pattern = unit.repeat(repeats);
+            pattern.push(b'X');
+            let mut text = unit.repeat(random.below(12) as usize);
+            text.extend_from_slice(&pattern[..random.below(pattern.len() as u64 + 1) as usize]);
+            text.extend(unit.repeat(random.below(3) as usize));
+            assert_eq!(two_way(&pattern, &text), linear_search(&pattern, &text),
+                       "{:?} in {:?}", pattern, text);
+        }
+    }
+}
@@ -0,0 +1,94 @@
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use core::cmp;
+
+// `z_array[i]` is the length of the longest common prefix
+// of the whole sequence and the suffix starting at `i`.
+// The first entry would trivially be the whole length, so it's zero.
+pub fn z_array<C: PartialEq>(s: &[C]) -> Vec<usize> {
+    let mut z_array = vec![0; s.len()];
+
+    // `s[l..r]` is the rightmost match of a prefix found so far.
+    let (mut l, mut r) = (0, 0);
+    for i in 1..s.len() {
+        // Inside that match, the suffix starts like the one at `i - l`,
+        // at least as far as the end of the match.
+        let mut len = if i < r { cmp::min(z_array[i - l], r - i) } else { 0 };
+        while i + len < s.len() && s[len] == s[i + len] {
+            len += 1;
+        }
+        z_array[i] = len;
+        if i + len > r {
+            l = i;
+            r = i + len;
+        }
+    }
+    z_array
+}
+
+// Searches with the Z-algorithm as if it was run over the pattern,
+// then a separator matching nothing, then the text. Rather than
+// building that sequence, the text is compared against the pattern
+// directly, and no prefix is extended past the end of the pattern,
+// which is all the separator would do.
+pub fn z_search<C: PartialEq>(pattern: &[C], text: &[C]) -> Option<usize> {
+    // Like `str::find`, an empty pattern matches at the start.
+    if pattern.is_empty() {
+        return Some(0);
+    }
+
+    let z_array = z_array(pattern);
+    // `text[l..r]` is the rightmost match of a prefix of the pattern.
+    let (mut l, mut r) = (0, 0);
+    for t in 0..text.len() {
+        let mut len = if t < r { cmp::min(z_array[t - l], r - t) } else { 0 };
+        while len < pattern.len() && t + len < text.len() && text[t + len] == pattern[len] {
+            len += 1;
+        }
+        if len == pattern.len() {
+            return Some(t);
+        }
+        if t + len > r {
+            l = t;
+            r = t + len;
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod correct_return {
+    use std::vec::Vec;
+    use super::{z_array, z_search};
+    use {kmp_search, border_table};
+    use correct_return::{CASES, TEXT, Random};
+
+    #[test]
+    fn z_array_reference() {
+        let s = "aabxaabxcaabxaabxay".as_bytes();
+        assert_eq!(z_array(s), vec![0, 1, 0, 0, 4, 1, 0, 0, 0, 8, 1, 0, 0, 5, 1, 0, 0, 1, 0]);
+        assert_eq!(z_array(b"aaaa"), vec![0, 3, 2, 1]);
+        assert_eq!(z_array::<u8>(b""), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn z_search_fixtures() {
+        for &(want, pattern) in CASES.iter() {
+            assert_eq!(z_search(pattern.as_bytes(), TEXT.as_bytes()), want);
+        }
+        assert_eq!(z_search(b"", b""), Some(0));
+        assert_eq!(z_search(b"aab", b"aaab"), Some(1));
+    }
+
+    #[test]
+    fn z_search_agrees_with_kmp() {
+        let mut random = Random(0x9e3779b97f4a7c15);
+        for _ in 0..10000 {
+            let pattern = random.bytes(6, 2);
+            let text = random.bytes(20, 2);
+            assert_eq!(z_search(&pattern, &text), kmp_search(&pattern, &text, &border_table(&pattern)),
+                       "{:?} in {:?}", pattern, text);
+        }
+    }
+}